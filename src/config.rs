@@ -1,10 +1,13 @@
 use std::str::FromStr;
 use std::time::Duration;
 
-use duration_str::deserialize_duration;
+use duration_str::{deserialize_duration, deserialize_option_duration};
 use reqwest::Method;
 use serde::{Deserialize, Serialize, Serializer};
 
+use crate::error::DominionConfigError;
+use crate::redact;
+
 const DEFAULT_SMTP_HOST: &str = "127.0.0.1";
 const DEFAULT_SMTP_PORT: u16 = lettre::transport::smtp::SMTP_PORT;
 
@@ -12,6 +15,19 @@ const fn default_smtp_port() -> u16 {
     DEFAULT_SMTP_PORT
 }
 
+/// The limit for bots in servers without boosts.
+const DEFAULT_DISCORD_MAX_ATTACHMENT_SIZE: u64 = 10 * 1024 * 1024;
+/// Attachments are encoded in base64, which makes them a third larger.
+const DEFAULT_MAIL_MAX_ATTACHMENT_SIZE: u64 = 15 * 1024 * 1024;
+
+const fn default_discord_max_attachment_size() -> u64 {
+    DEFAULT_DISCORD_MAX_ATTACHMENT_SIZE
+}
+
+const fn default_mail_max_attachment_size() -> u64 {
+    DEFAULT_MAIL_MAX_ATTACHMENT_SIZE
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     /// Interval between heartbeats
@@ -21,7 +37,11 @@ pub struct Config {
     )]
     pub heartbeat: Duration,
     pub log: LogConfig,
+    #[serde(default)]
+    pub store: StoreConfig,
     pub http: HttpConfig,
+    #[serde(default)]
+    pub redact: RedactConfig,
     #[cfg(feature = "discord")]
     pub discord: DiscordConfig,
     #[cfg(feature = "email")]
@@ -38,7 +58,9 @@ impl Default for Config {
                 level: "warn,dominion=info".to_string(),
                 file: None,
             },
+            store: StoreConfig::default(),
             http: HttpConfig::default(),
+            redact: RedactConfig::default(),
             #[cfg(feature = "discord")]
             discord: DiscordConfig::default(),
             #[cfg(feature = "email")]
@@ -47,28 +69,95 @@ impl Default for Config {
                 WatchEntry {
                     protocol: "http".to_string(),
                     url: "https://example.com".to_string(),
+                    id: None,
                     method: Method::GET,
                     headers: vec![],
+                    body: None,
+                    body_file: None,
+                    json: None,
+                    auth: None,
+                    session: None,
+                    http: HttpConfig::default(),
+                    args: vec![],
+                    env: vec![],
+                    timeout: default_timeout(),
+                    expiry_threshold: default_expiry_threshold(),
+                    records: default_records(),
+                    resolver: None,
                     interval: Duration::from_secs(30),
                     variation: 0.25, // 25% - 1h requests will be in the range of 1h-1h15m
                     stagger: Duration::from_secs(5),
+                    json_query: None,
+                    ignore_paths: vec![],
+                    ignore_paths_mode: IgnoreMode::default(),
+                    normalize: NormalizeConfig::default(),
+                    selectors: vec![],
+                    select_as: SelectAs::default(),
+                    render: Render::default(),
+                    include: vec![],
                     ignore: vec![],
+                    history: HistoryConfig::default(),
+                    expect: None,
+                    unresolved: None,
                 },
                 WatchEntry {
                     protocol: "http".to_string(),
                     url: "https://example2.com".to_string(),
+                    id: None,
                     method: Method::GET,
                     headers: vec![],
+                    body: None,
+                    body_file: None,
+                    json: None,
+                    auth: None,
+                    session: None,
+                    http: HttpConfig::default(),
+                    args: vec![],
+                    env: vec![],
+                    timeout: default_timeout(),
+                    expiry_threshold: default_expiry_threshold(),
+                    records: default_records(),
+                    resolver: None,
                     interval: Duration::from_secs(60 * 10), // 10 minutes
                     variation: default_variation(),
                     stagger: default_stagger(),
+                    json_query: None,
+                    ignore_paths: vec![],
+                    ignore_paths_mode: IgnoreMode::default(),
+                    normalize: NormalizeConfig::default(),
+                    selectors: vec![],
+                    select_as: SelectAs::default(),
+                    render: Render::default(),
+                    include: vec![],
                     ignore: vec![],
+                    history: HistoryConfig::default(),
+                    expect: None,
+                    unresolved: None,
                 },
             ],
         }
     }
 }
 
+impl Config {
+    /// Replaces the `${env:NAME}` and `${file:/path}` references in the values that may hold
+    /// secrets, so that these can be kept out of the config file.
+    pub fn resolve_secrets(&mut self) -> Result<(), DominionConfigError> {
+        #[cfg(feature = "discord")]
+        resolve_secret(&mut self.discord.token)?;
+        #[cfg(feature = "email")]
+        {
+            resolve_secret(&mut self.email.smtp_username)?;
+            resolve_secret(&mut self.email.smtp_password)?;
+        }
+
+        for entry in &mut self.watch {
+            entry.resolve_secrets()?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct LogConfig {
     pub enabled: bool,
@@ -77,8 +166,124 @@ pub struct LogConfig {
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StoreConfig {
+    /// Directory where the watchers state is persisted.
+    /// Defaults to the user's local data directory.
+    pub dir: Option<String>,
+}
+
+/// Settings of the HTTP client. Those of a watch entry override the global ones.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct HttpConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_agent: Option<String>,
+    /// Maximum time to establish a connection. Defaults to 30 seconds.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "deserialize_option_duration"
+    )]
+    pub connect_timeout: Option<Duration>,
+    /// Maximum time to wait for the server to send data. Defaults to 60 seconds.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "deserialize_option_duration"
+    )]
+    pub read_timeout: Option<Duration>,
+    /// Maximum time for the whole request, until the response has been fully received.
+    /// Defaults to 5 minutes.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "deserialize_option_duration"
+    )]
+    pub timeout: Option<Duration>,
+    /// URL of an HTTP or SOCKS proxy, e.g. `socks5://localhost:1080`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// One of: follow, none, or the maximum number of redirects to follow. Defaults to follow.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_redirect",
+        deserialize_with = "deserialize_option_redirect"
+    )]
+    pub redirect: Option<RedirectPolicy>,
+    /// Path of a PEM file with extra certificate authorities to trust.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    /// Path of a PEM file with the client certificate chain, to authenticate with the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+    /// Path of a PEM file with the PKCS#8 private key of the client certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    /// Accepts invalid certificates, e.g. expired or self-signed. Dangerous.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept_invalid_certs: Option<bool>,
+}
+
+impl HttpConfig {
+    /// Combines these settings with the ones of a watch entry, which take precedence.
+    pub fn merge(&self, overrides: &HttpConfig) -> HttpConfig {
+        HttpConfig {
+            user_agent: overrides
+                .user_agent
+                .clone()
+                .or_else(|| self.user_agent.clone()),
+            connect_timeout: overrides.connect_timeout.or(self.connect_timeout),
+            read_timeout: overrides.read_timeout.or(self.read_timeout),
+            timeout: overrides.timeout.or(self.timeout),
+            proxy: overrides.proxy.clone().or_else(|| self.proxy.clone()),
+            redirect: overrides.redirect.or(self.redirect),
+            ca_bundle: overrides
+                .ca_bundle
+                .clone()
+                .or_else(|| self.ca_bundle.clone()),
+            client_cert: overrides
+                .client_cert
+                .clone()
+                .or_else(|| self.client_cert.clone()),
+            client_key: overrides
+                .client_key
+                .clone()
+                .or_else(|| self.client_key.clone()),
+            accept_invalid_certs: overrides.accept_invalid_certs.or(self.accept_invalid_certs),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RedirectPolicy {
+    /// Follows up to 10 redirects.
+    #[default]
+    Follow,
+    None,
+    Limit(usize),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedactConfig {
+    /// Headers whose values are masked in logs and notifications, along with any credentials and
+    /// values read from environment variables or files.
+    pub headers: Vec<String>,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            headers: vec![
+                "Authorization".to_string(),
+                "Proxy-Authorization".to_string(),
+                "Cookie".to_string(),
+                "X-Api-Key".to_string(),
+            ],
+        }
+    }
 }
 
 #[cfg(feature = "discord")]
@@ -94,6 +299,10 @@ pub struct DiscordConfig {
     /// ID of the first message to delete (exclusive).
     #[serde(default)]
     pub purge_after: u64,
+    /// Maximum total size of the files attached to a message, in bytes. Changed files that do not
+    /// fit are only described.
+    #[serde(default = "default_discord_max_attachment_size")]
+    pub max_attachment_size: u64,
 }
 
 impl Default for DiscordConfig {
@@ -103,6 +312,7 @@ impl Default for DiscordConfig {
             token: "".to_string(),
             purge: false,
             purge_after: 0,
+            max_attachment_size: DEFAULT_DISCORD_MAX_ATTACHMENT_SIZE,
         }
     }
 }
@@ -122,6 +332,10 @@ pub struct MailConfig {
     pub smtp_password: String,
     pub from_address: String,
     pub to_address: String,
+    /// Maximum total size of the files attached to a mail, in bytes. Changed files that do not
+    /// fit are only described.
+    #[serde(default = "default_mail_max_attachment_size")]
+    pub max_attachment_size: u64,
 }
 
 #[cfg(feature = "email")]
@@ -136,17 +350,29 @@ impl Default for MailConfig {
             smtp_password: "".to_string(),
             from_address: "Dominion <dominion@example.com>".to_string(),
             to_address: "".to_string(),
+            max_attachment_size: DEFAULT_MAIL_MAX_ATTACHMENT_SIZE,
         }
     }
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct WatchEntry {
+    /// One of: http, feed, sitemap, file, command, tls, dns
     #[serde(default = "default_protocol", skip_serializing_if = "skip_protocol")]
     pub protocol: String,
 
-    // HTTP params
+    /// What to watch. For the `feed` protocol, this is the URL of an RSS or Atom feed, for the
+    /// `sitemap` protocol the URL of a sitemap or sitemap index, for the `file` protocol the path
+    /// of a file or directory, for the `command` protocol the program to run, for the `tls`
+    /// protocol the 'host:port' to connect to, and for the `dns` protocol the domain name to
+    /// resolve.
     pub url: String,
+    /// Identifies the entry in the store. Defaults to a hash of the settings that affect the
+    /// content, so changing any of them starts over from a new baseline; set this to keep it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    // HTTP params
     #[serde(
         default = "default_method",
         skip_serializing_if = "skip_method",
@@ -156,6 +382,60 @@ pub struct WatchEntry {
     pub method: Method,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub headers: Vec<String>,
+    /// Request body, sent as JSON if it is valid JSON, or as plain text otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Path of a file to send as the request body, read before every request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<String>,
+    /// Request body to send as JSON, e.g. a GraphQL query. Only one of `body`, `body_file` and
+    /// `json` may be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+    /// Credentials to authenticate the requests with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+    /// Login flow to run before fetching, and again whenever the session expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionConfig>,
+    /// Overrides the global HTTP client settings.
+    #[serde(default, skip_serializing_if = "skip_http")]
+    pub http: HttpConfig,
+
+    // Command params
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Environment variables, in the format 'name=value'.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// Maximum time a command is allowed to run for, or a TLS connection or DNS query is allowed
+    /// to take.
+    #[serde(
+        default = "default_timeout",
+        skip_serializing_if = "skip_timeout",
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub timeout: Duration,
+
+    // TLS params
+    /// Warns when a certificate is about to expire within this duration.
+    #[serde(
+        default = "default_expiry_threshold",
+        skip_serializing_if = "skip_expiry_threshold",
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub expiry_threshold: Duration,
+
+    // DNS params
+    /// Types of the records to resolve, e.g. A, AAAA, CNAME, MX, TXT or NS.
+    #[serde(default = "default_records", skip_serializing_if = "skip_records")]
+    pub records: Vec<String>,
+    /// Address of the name server, in the format 'ip' or 'ip:port'.
+    /// Defaults to the system configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolver: Option<String>,
 
     #[serde(
         serialize_with = "serialize_duration",
@@ -175,9 +455,241 @@ pub struct WatchEntry {
         deserialize_with = "deserialize_duration"
     )]
     pub stagger: Duration,
+    /// Only watches the parts of a JSON document matched by this JSONPath expression,
+    /// e.g. `$.items[*].price`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_query: Option<String>,
+    /// Ignores any changes to the keys of a JSON document matched by these paths,
+    /// e.g. `data.items.*.updatedAt`. A `*` matches any key, and `**` any number of nested keys,
+    /// so it must be followed by the key to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_paths: Vec<String>,
+    /// Whether the keys matched by `ignore_paths` are removed or have their values blanked.
+    #[serde(default, skip_serializing_if = "skip_ignore_paths_mode")]
+    pub ignore_paths_mode: IgnoreMode,
+    /// Normalizes JSON documents, so that only semantic changes are picked up.
+    #[serde(default, skip_serializing_if = "skip_normalize")]
+    pub normalize: NormalizeConfig,
+    /// Only watches the HTML elements matched by these CSS selectors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selectors: Vec<String>,
+    /// Whether the elements matched by `selectors` are kept as text or as HTML.
+    #[serde(default, skip_serializing_if = "skip_select_as")]
+    pub select_as: SelectAs,
+    /// How the content is rendered before being compared. Use `text` to turn HTML into readable
+    /// plain text, or `pdf-text` to extract the text of PDF documents.
+    #[serde(default, skip_serializing_if = "skip_render")]
+    pub render: Render,
+    /// Response headers to watch along with the content, e.g. `location` or `cache-control`.
+    /// Use `status` to also watch the status line. Unless configured otherwise, any status is then
+    /// accepted and redirects are not followed, which also applies to `location`. Not supported
+    /// by the feed and sitemap protocols.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
     /// Ignores any changes to the patterns provided here. Can be any regular expression.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ignore: Vec<String>,
+    /// Retention policy of the change history.
+    #[serde(default, skip_serializing_if = "skip_history")]
+    pub history: HistoryConfig,
+    /// Conditions the response must meet, otherwise the fetch is reported as failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<ExpectConfig>,
+    /// The entry as configured, before its secrets were resolved, so that it is identified the
+    /// same way whatever their values.
+    #[serde(skip)]
+    pub unresolved: Option<Box<WatchEntry>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectAs {
+    #[default]
+    Html,
+    Text,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnoreMode {
+    #[default]
+    Remove,
+    Blank,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Render {
+    /// Content is kept as is.
+    #[default]
+    Raw,
+    /// HTML is converted into plain text.
+    Text,
+    /// The text of PDF documents is extracted, page by page. Fails on content that is not a PDF.
+    PdfText,
+}
+
+impl WatchEntry {
+    fn resolve_secrets(&mut self) -> Result<(), DominionConfigError> {
+        self.unresolved = Some(Box::new(self.clone()));
+
+        resolve_secret(&mut self.url)?;
+        self.headers.iter_mut().try_for_each(resolve_secret)?;
+        self.env.iter_mut().try_for_each(resolve_secret)?;
+        self.body.iter_mut().try_for_each(resolve_secret)?;
+        self.json.iter_mut().try_for_each(resolve_json_secrets)?;
+
+        match &mut self.auth {
+            Some(AuthConfig::Basic { password, .. }) => {
+                password.iter_mut().try_for_each(resolve_secret)?;
+            }
+            Some(AuthConfig::Bearer { token }) => resolve_secret(token)?,
+            Some(AuthConfig::OAuth2 { client_secret, .. }) => resolve_secret(client_secret)?,
+            None => {}
+        }
+
+        if let Some(session) = &mut self.session {
+            for step in &mut session.steps {
+                step.headers.iter_mut().try_for_each(resolve_secret)?;
+                step.form.iter_mut().try_for_each(resolve_secret)?;
+                step.body.iter_mut().try_for_each(resolve_secret)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+    /// HTTP basic authentication.
+    Basic {
+        username: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    /// A static bearer token.
+    Bearer { token: String },
+    /// A bearer token obtained through the OAuth2 client credentials flow, and renewed before it
+    /// expires.
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        scopes: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Requests to make in order, sharing their cookies with the main request.
+    pub steps: Vec<SessionStep>,
+    /// Redirects to this URL mean the session expired. Defaults to the URL of the first step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SessionStep {
+    /// May include values extracted by previous steps, e.g. `${csrf}`. The same applies to the
+    /// headers, form and body.
+    pub url: String,
+    #[serde(
+        default = "default_method",
+        skip_serializing_if = "skip_method",
+        serialize_with = "serialize_method",
+        deserialize_with = "deserialize_method"
+    )]
+    pub method: Method,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+    /// Form fields, in the format 'name=value'.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Values to extract from the response body, in the format 'name=regex'. The first capture
+    /// group is used if there is one, or the whole match otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extract: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NormalizeConfig {
+    /// Sorts the keys of objects, so that reordering them is not a change. Enabled by default.
+    /// Otherwise, keys are kept in the order of the document.
+    #[serde(default = "default_sort_keys")]
+    pub sort_keys: bool,
+    /// Sorts arrays of scalar values, e.g. strings or numbers.
+    #[serde(default)]
+    pub sort_arrays: bool,
+    /// Sorts arrays of objects by the value of this key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_arrays_by: Option<String>,
+    /// Removes keys with null values.
+    #[serde(default)]
+    pub drop_nulls: bool,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        Self {
+            sort_keys: default_sort_keys(),
+            sort_arrays: false,
+            sort_arrays_by: None,
+            drop_nulls: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExpectConfig {
+    /// Allowed HTTP status codes, as codes (`404`), ranges (`200-299`) or classes (`3xx`).
+    /// Defaults to any 2xx status. Only for the http, feed and sitemap protocols.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<String>,
+    /// Texts the body must contain. Binary content is rendered first, e.g. with `pdf-text`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body_contains: Vec<String>,
+    /// Regular expressions the body must match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body_matches: Vec<String>,
+    /// Headers the response must have, in the format 'name=value'.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "deserialize_option_duration"
+    )]
+    pub max_response_time: Option<Duration>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Maximum number of changes to keep. Set to 0 to disable the history.
+    #[serde(default = "default_history_max_entries")]
+    pub max_entries: usize,
+    /// Changes older than this are discarded. Kept forever if not set.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "deserialize_option_duration"
+    )]
+    pub max_age: Option<Duration>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_history_max_entries(),
+            max_age: None,
+        }
+    }
 }
 
 fn default_protocol() -> String {
@@ -188,6 +700,133 @@ fn skip_protocol(value: &String) -> bool {
     value.is_empty() || value == &default_protocol()
 }
 
+fn default_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn skip_timeout(value: &Duration) -> bool {
+    *value == default_timeout()
+}
+
+fn default_expiry_threshold() -> Duration {
+    Duration::from_secs(60 * 60 * 24 * 14) // 14 days
+}
+
+fn skip_expiry_threshold(value: &Duration) -> bool {
+    *value == default_expiry_threshold()
+}
+
+fn default_records() -> Vec<String> {
+    vec!["A".to_string(), "AAAA".to_string()]
+}
+
+fn skip_records(value: &Vec<String>) -> bool {
+    value == &default_records()
+}
+
+fn skip_ignore_paths_mode(value: &IgnoreMode) -> bool {
+    *value == IgnoreMode::default()
+}
+
+fn skip_normalize(value: &NormalizeConfig) -> bool {
+    value == &NormalizeConfig::default()
+}
+
+const fn default_sort_keys() -> bool {
+    true
+}
+
+fn skip_render(value: &Render) -> bool {
+    *value == Render::default()
+}
+
+fn skip_select_as(value: &SelectAs) -> bool {
+    *value == SelectAs::default()
+}
+
+fn default_history_max_entries() -> usize {
+    100
+}
+
+fn skip_http(value: &HttpConfig) -> bool {
+    value == &HttpConfig::default()
+}
+
+fn skip_history(value: &HistoryConfig) -> bool {
+    value == &HistoryConfig::default()
+}
+
+/// Splits a `name=value` pair, as used by headers and the like. The kind and format describe the
+/// pair in the error, as the value itself may be a secret.
+pub fn parse_pair(
+    value: &str,
+    kind: &str,
+    format: &str,
+) -> Result<(String, String), DominionConfigError> {
+    let (name, value) =
+        value
+            .split_once('=')
+            .ok_or_else(|| DominionConfigError::MalformedPair {
+                kind: kind.to_string(),
+                format: format.to_string(),
+            })?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Resolves the secret references in every string of a JSON document, see [resolve_secret].
+fn resolve_json_secrets(value: &mut serde_json::Value) -> Result<(), DominionConfigError> {
+    match value {
+        serde_json::Value::String(text) => resolve_secret(text),
+        serde_json::Value::Array(vec) => vec.iter_mut().try_for_each(resolve_json_secrets),
+        serde_json::Value::Object(map) => map.values_mut().try_for_each(resolve_json_secrets),
+        _ => Ok(()),
+    }
+}
+
+/// Replaces every `${env:NAME}` and `${file:/path}` reference in a value with the content of the
+/// environment variable or file. Any other placeholders, like those of session steps, are kept.
+pub fn resolve_secret(value: &mut String) -> Result<(), DominionConfigError> {
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value.as_str();
+
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let Some(end) = reference.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let (kind, name) = reference[..end].split_once(':').unwrap_or(("", ""));
+        match kind {
+            "env" => {
+                let secret = std::env::var(name).map_err(|_| DominionConfigError::MissingEnv {
+                    name: name.to_string(),
+                })?;
+                redact::register(secret.as_str());
+                resolved.push_str(secret.as_str());
+            }
+            "file" => {
+                let secret =
+                    std::fs::read_to_string(name).map_err(|e| DominionConfigError::SecretFile {
+                        path: name.to_string(),
+                        source: e,
+                    })?;
+                // Files usually end with a newline, which is never part of the secret
+                let secret = secret.trim_end_matches(['\r', '\n']);
+                redact::register(secret);
+                resolved.push_str(secret);
+            }
+            _ => resolved.push_str(&rest[start..start + 2 + end + 1]),
+        }
+        rest = &reference[end + 1..];
+    }
+    resolved.push_str(rest);
+
+    *value = resolved;
+    Ok(())
+}
+
 fn default_method() -> Method {
     Method::GET
 }
@@ -228,6 +867,64 @@ where
     deserializer.deserialize_any(MethodDeserializer)
 }
 
+fn serialize_option_redirect<S>(value: &Option<RedirectPolicy>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(RedirectPolicy::Follow) => s.serialize_str("follow"),
+        Some(RedirectPolicy::None) => s.serialize_str("none"),
+        Some(RedirectPolicy::Limit(max)) => s.serialize_u64(*max as u64),
+        None => s.serialize_none(),
+    }
+}
+
+struct RedirectDeserializer;
+
+impl<'de> serde::de::Visitor<'de> for RedirectDeserializer {
+    type Value = Option<RedirectPolicy>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("expected 'follow', 'none' or the maximum number of redirects")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match s {
+            "follow" => Ok(Some(RedirectPolicy::Follow)),
+            "none" => Ok(Some(RedirectPolicy::None)),
+            _ => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(s),
+                &self,
+            )),
+        }
+    }
+
+    fn visit_u64<E>(self, max: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Some(RedirectPolicy::Limit(max as usize)))
+    }
+
+    fn visit_i64<E>(self, max: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let max = u64::try_from(max).map_err(serde::de::Error::custom)?;
+        self.visit_u64(max)
+    }
+}
+
+fn deserialize_option_redirect<'de, D>(deserializer: D) -> Result<Option<RedirectPolicy>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_any(RedirectDeserializer)
+}
+
 fn default_variation() -> f32 {
     0.0
 }
@@ -317,3 +1014,87 @@ where
     let str = format_duration(value);
     s.serialize_str(str.as_str())
 }
+
+fn serialize_option_duration<S>(value: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => serialize_duration(value, s),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::*;
+
+    #[test]
+    fn http_overrides() {
+        let global = serde_json::from_str::<HttpConfig>(
+            r#"{ "read_timeout": "30s", "redirect": "none", "proxy": "socks5://localhost:1080" }"#,
+        )
+        .unwrap();
+        let entry =
+            serde_json::from_str::<HttpConfig>(r#"{ "redirect": 3, "timeout": "2m" }"#).unwrap();
+
+        let merged = global.merge(&entry);
+        assert_eq!(merged.read_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(merged.timeout, Some(Duration::from_secs(120)));
+        assert_eq!(merged.redirect, Some(RedirectPolicy::Limit(3)));
+        assert_eq!(merged.proxy.as_deref(), Some("socks5://localhost:1080"));
+    }
+
+    #[test]
+    fn secrets() {
+        let file = std::env::temp_dir().join("dominion-test-secret");
+        std::fs::write(&file, "hunter2\n").unwrap();
+
+        let mut value = format!(
+            "${{env:CARGO_PKG_NAME}}:${{file:{}}} ${{csrf}}",
+            file.display()
+        );
+        resolve_secret(&mut value).unwrap();
+        assert_eq!(value, "dominion:hunter2 ${csrf}");
+
+        let mut value = "prefix ${env:UNTERMINATED".to_string();
+        resolve_secret(&mut value).unwrap();
+        assert_eq!(value, "prefix ${env:UNTERMINATED");
+
+        let mut value = "${env:DOMINION_TEST_UNDEFINED}".to_string();
+        assert!(matches!(
+            resolve_secret(&mut value),
+            Err(DominionConfigError::MissingEnv { .. })
+        ));
+
+        let mut entry = WatchEntry {
+            json: Some(serde_json::json!({
+                "query": "{ items }",
+                "variables": { "tokens": ["${env:CARGO_PKG_NAME}", 42] }
+            })),
+            ..WatchEntry::default()
+        };
+        entry.resolve_secrets().unwrap();
+        assert_eq!(
+            entry.json,
+            Some(serde_json::json!({
+                "query": "{ items }",
+                "variables": { "tokens": ["dominion", 42] }
+            }))
+        );
+    }
+
+    #[test]
+    fn pair() {
+        assert_eq!(
+            parse_pair("Accept=text/html; q=0.9", "header", "name=value").unwrap(),
+            ("Accept".to_string(), "text/html; q=0.9".to_string())
+        );
+        assert_eq!(
+            parse_pair("Accept: text/html", "header", "name=value")
+                .unwrap_err()
+                .to_string(),
+            "malformed header; should be 'name=value'"
+        );
+    }
+}
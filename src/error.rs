@@ -17,6 +17,8 @@ pub enum DominionError {
     Mail(#[from] DominionMailError),
     #[error("request error: {0}")]
     Request(#[from] DominionRequestError),
+    #[error("store error: {0}")]
+    Store(#[from] DominionStoreError),
     #[error(transparent)]
     Unknown(#[from] Box<dyn std::error::Error + Send>),
 }
@@ -45,6 +47,57 @@ pub enum DominionRequestError {
     },
     #[error("regex error: {0}")]
     Regex(#[from] regex::Error),
+    #[error("render error: {0}")]
+    Render(String),
+    #[error("error running command '{command}': {source}")]
+    CommandSpawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("command '{command}' timed out after {timeout}")]
+    CommandTimeout { command: String, timeout: String },
+    #[error("command '{command}' failed with {status} and stderr: {stderr}")]
+    CommandFailed {
+        command: String,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    #[error("invalid address '{address}'; should be 'host:port'")]
+    InvalidAddress { address: String },
+    #[error("error connecting to {address}: {source}")]
+    Connect {
+        address: String,
+        source: std::io::Error,
+    },
+    #[error("connection to {address} timed out after {timeout}")]
+    Timeout { address: String, timeout: String },
+    #[error("TLS error: {0}")]
+    Tls(#[from] rustls::Error),
+    #[error("invalid server name: {0}")]
+    InvalidServerName(#[from] rustls::pki_types::InvalidDnsNameError),
+    #[error("certificate error: {0}")]
+    Certificate(String),
+    #[error("DNS error: {0}")]
+    Dns(#[from] hickory_resolver::ResolveError),
+    #[error("error reading '{path}': {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("login step #{step} on {url} failed: {reason}")]
+    SessionFailed {
+        step: usize,
+        url: String,
+        reason: String,
+    },
+    #[error("{url} did not meet expectations: {reason}")]
+    ExpectationFailed { url: String, reason: String },
+    #[error("feed error: {0}")]
+    Feed(#[from] feed_rs::parser::ParseFeedError),
+    #[error("XML error: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("invalid sitemap {url}: {reason}")]
+    InvalidSitemap { url: String, reason: String },
 }
 
 #[cfg(feature = "discord")]
@@ -82,6 +135,52 @@ pub enum DominionConfigError {
         file: String,
         source: confy::ConfyError,
     },
+    #[error("unknown protocol '{protocol}' for {url}")]
+    UnknownProtocol { url: String, protocol: String },
+    #[error("unknown DNS record type '{record}' for {url}")]
+    UnknownRecordType { url: String, record: String },
+    #[error("invalid CSS selector '{selector}': {reason}")]
+    InvalidSelector { selector: String, reason: String },
+    #[error("invalid JSONPath query '{query}': {reason}")]
+    InvalidJsonQuery { query: String, reason: String },
+    #[error("invalid ignore path '{path}': {reason}")]
+    InvalidIgnorePath { path: String, reason: String },
+    #[error("only one of 'body', 'body_file' and 'json' may be set for {url}")]
+    ConflictingBodies { url: String },
+    #[error(
+        "invalid status '{status}'; should be a code, a range like '200-299' or a class like '2xx'"
+    )]
+    InvalidStatus { status: String },
+    #[error("malformed {kind}; should be '{format}'")]
+    MalformedPair { kind: String, format: String },
+    #[error("both 'client_cert' and 'client_key' must be set for {url}")]
+    IncompleteClientIdentity { url: String },
+    #[error("'{option}' is not supported by the {protocol} protocol, for {url}")]
+    UnsupportedOption {
+        url: String,
+        protocol: String,
+        option: String,
+    },
+    #[error("environment variable '{name}' is not set")]
+    MissingEnv { name: String },
+    #[error("error reading secret from '{path}': {source}")]
+    SecretFile {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum DominionStoreError {
+    #[error("error creating directory '{dir}': {source}")]
+    CreateDir { dir: String, source: std::io::Error },
+    #[error("error accessing '{file}': {source}")]
+    Io {
+        file: String,
+        source: std::io::Error,
+    },
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 #[derive(Error, Debug)]
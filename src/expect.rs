@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::StatusCode;
+
+use crate::config::{format_duration, parse_pair, ExpectConfig};
+use crate::error::{DominionConfigError, DominionError, DominionRequestError};
+use crate::source::Response;
+
+/// An inclusive range of accepted HTTP status codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusRange(u16, u16);
+
+impl StatusRange {
+    pub fn parse(status: &str) -> Result<Self, DominionConfigError> {
+        let invalid = || DominionConfigError::InvalidStatus {
+            status: status.to_string(),
+        };
+        let code = |value: &str| value.trim().parse::<u16>().map_err(|_| invalid());
+
+        let status = status.trim();
+        let range = if let Some((from, to)) = status.split_once('-') {
+            StatusRange(code(from)?, code(to)?)
+        } else if let Some(class) = status
+            .strip_suffix("xx")
+            .or_else(|| status.strip_suffix("XX"))
+        {
+            let class = code(class)?;
+            if !(1..=5).contains(&class) {
+                return Err(invalid());
+            }
+            StatusRange(class * 100, class * 100 + 99)
+        } else {
+            let code = code(status)?;
+            StatusRange(code, code)
+        };
+
+        if range.0 > range.1 || StatusCode::from_u16(range.0).is_err() {
+            return Err(invalid());
+        }
+        Ok(range)
+    }
+
+    /// Accepts any status, e.g. when the status itself is watched.
+    pub fn any() -> Self {
+        StatusRange(100, 999)
+    }
+
+    pub fn contains(&self, status: StatusCode) -> bool {
+        (self.0..=self.1).contains(&status.as_u16())
+    }
+}
+
+/// Conditions that a response must meet for the fetch to be considered successful.
+///
+/// The accepted statuses are checked by the HTTP source itself, as it is the one that knows them.
+#[derive(Debug)]
+pub struct Expectations {
+    body_contains: Vec<String>,
+    body_matches: Vec<Regex>,
+    headers: Vec<(String, String)>,
+    max_response_time: Option<Duration>,
+}
+
+impl Expectations {
+    pub fn new(cfg: &ExpectConfig) -> Result<Self, DominionError> {
+        Ok(Self {
+            body_contains: cfg.body_contains.clone(),
+            body_matches: cfg
+                .body_matches
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<_, _>>()
+                .map_err(DominionRequestError::from)?,
+            headers: cfg
+                .headers
+                .iter()
+                .map(|h| parse_pair(h, "expected header", "name=value"))
+                .collect::<Result<_, _>>()?,
+            max_response_time: cfg.max_response_time,
+        })
+    }
+
+    /// Checks how long a response took and its headers, as fetched from the source. Headers that
+    /// are missing are only an issue if the content was sent, as a 304 may leave them out.
+    pub fn check(&self, response: &Response, elapsed: Duration) -> Result<(), String> {
+        if let Some(max) = self.max_response_time
+            && elapsed > max
+        {
+            return Err(format!(
+                "response took {}, more than the maximum of {}",
+                format_duration(&elapsed),
+                format_duration(&max)
+            ));
+        }
+
+        for (name, expected) in &self.headers {
+            match response.metadata(name) {
+                Some(value) if value == expected => {}
+                Some(value) => {
+                    return Err(format!(
+                        "header '{name}' is '{value}' instead of '{expected}'"
+                    ))
+                }
+                None if response.is_not_modified() => {}
+                None => return Err(format!("header '{name}' is missing")),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether there are any expectations on the body, which may be costly to get as text.
+    pub fn checks_body(&self) -> bool {
+        !self.body_contains.is_empty() || !self.body_matches.is_empty()
+    }
+
+    /// Checks the body of a response as text, before any transformation.
+    pub fn check_body(&self, body: &str) -> Result<(), String> {
+        for text in &self.body_contains {
+            if !body.contains(text.as_str()) {
+                return Err(format!("body does not contain '{text}'"));
+            }
+        }
+
+        for regex in &self.body_matches {
+            if !regex.is_match(body) {
+                return Err(format!("body does not match '{regex}'"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expect::*;
+    use crate::source::Status;
+
+    #[test]
+    fn status() {
+        let range = StatusRange::parse("3xx").unwrap();
+        assert!(range.contains(StatusCode::MOVED_PERMANENTLY));
+        assert!(!range.contains(StatusCode::OK));
+
+        assert_eq!(StatusRange::parse(" 404 ").unwrap(), StatusRange(404, 404));
+        assert_eq!(
+            StatusRange::parse("200-204").unwrap(),
+            StatusRange(200, 204)
+        );
+        assert!(StatusRange::parse("204-200").is_err());
+        assert!(StatusRange::parse("ok").is_err());
+        assert!(StatusRange::parse("700xx").is_err());
+        assert!(StatusRange::parse("0xx").is_err());
+    }
+
+    #[test]
+    fn check() {
+        let expectations = Expectations::new(&ExpectConfig {
+            body_contains: vec!["healthy".to_string()],
+            body_matches: vec![r#""version": "\d+""#.to_string()],
+            headers: vec!["content-type=application/json".to_string()],
+            max_response_time: Some(Duration::from_secs(1)),
+            ..ExpectConfig::default()
+        })
+        .unwrap();
+        let response = Response {
+            content: String::new(),
+            status: None,
+            metadata: vec![("Content-Type".to_string(), "application/json".to_string())],
+            warnings: vec![],
+            binary: None,
+            elapsed: None,
+        };
+
+        assert_eq!(
+            expectations.check(&response, Duration::from_millis(10)),
+            Ok(())
+        );
+        assert_eq!(
+            expectations.check(&response, Duration::from_secs(2)),
+            Err("response took 2s, more than the maximum of 1s".to_string())
+        );
+
+        let response = Response {
+            metadata: vec![],
+            ..response
+        };
+        assert_eq!(
+            expectations.check(&response, Duration::from_millis(10)),
+            Err("header 'content-type' is missing".to_string())
+        );
+        let response = Response {
+            status: Some(Status::Http(StatusCode::NOT_MODIFIED)),
+            ..response
+        };
+        assert_eq!(
+            expectations.check(&response, Duration::from_millis(10)),
+            Ok(())
+        );
+
+        assert!(expectations.checks_body());
+        assert_eq!(
+            expectations.check_body(r#"{ "status": "healthy", "version": "3" }"#),
+            Ok(())
+        );
+        assert_eq!(
+            expectations.check_body(r#"{ "status": "degraded", "version": "3" }"#),
+            Err("body does not contain 'healthy'".to_string())
+        );
+    }
+}
@@ -4,7 +4,6 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use directories::ProjectDirs;
-use reqwest::StatusCode;
 use tokio::sync::mpsc::Sender;
 use tracing::log::LevelFilter;
 use tracing::{debug, info, trace};
@@ -18,10 +17,18 @@ use watch::Watcher;
 
 use crate::config::{Config, WatchEntry};
 use crate::error::{DominionAsyncError, DominionConfigError, DominionError, DominionLogError};
+use crate::redact::Redacting;
+use crate::source::{BinaryContent, Status};
+use crate::store::Store;
 
 mod config;
 mod error;
+mod expect;
 mod notify;
+mod redact;
+mod source;
+mod store;
+mod transform;
 mod watch;
 
 #[derive(Debug)]
@@ -34,30 +41,54 @@ pub enum NotificationEvent {
         old: String,
         new: String,
     },
+    BinaryChanged {
+        url: String,
+        old: Option<Box<BinaryContent>>,
+        new: Box<BinaryContent>,
+    },
+    NewItem {
+        url: String,
+        title: Option<String>,
+        link: Option<String>,
+        summary: Option<String>,
+    },
+    SitemapChanged {
+        url: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+        modified: Vec<String>,
+    },
     NoChanges {
         url: String,
     },
+    Warning {
+        url: String,
+        message: String,
+    },
     Failed {
         url: String,
         reason: String,
-        status: Option<StatusCode>,
+        status: Option<Status>,
         body: Option<String>,
     },
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), DominionError> {
-    let (cfg_dir, log_dir) = dirs()?;
+    let (cfg_dir, log_dir, data_dir) = dirs()?;
 
     let (cfg, cfg_file) = load_config(cfg_dir)?;
+    redact::register_config(&cfg);
     let _log_guard = init_log(&cfg, log_dir)?;
     info!("Loaded config from '{}'", cfg_file.display());
 
+    let store = init_store(&cfg, data_dir)?;
+
     let urls = cfg.watch.iter().map(|w| w.url.clone()).collect();
 
     let tx = notify::prepare_notifier(&cfg).await?;
     for entry in &cfg.watch {
-        prepare_watcher(entry, tx.clone(), &cfg)?;
+        prepare_watcher(entry, tx.clone(), &cfg, store.clone()).await?;
     }
 
     info!("Dominion started");
@@ -69,14 +100,15 @@ async fn main() -> Result<(), DominionError> {
     Ok(())
 }
 
-fn dirs() -> Result<(PathBuf, PathBuf), DominionError> {
+fn dirs() -> Result<(PathBuf, PathBuf, PathBuf), DominionError> {
     let dirs = ProjectDirs::from("net", "SandroHc", "dominion")
         .ok_or(DominionConfigError::BadConfigDirectory)?;
 
     let config_dir = dirs.config_dir().to_path_buf();
     let log_dir = dirs.data_local_dir().join("logs");
+    let data_dir = dirs.data_local_dir().to_path_buf();
 
-    Ok((config_dir, log_dir))
+    Ok((config_dir, log_dir, data_dir))
 }
 
 /// Loads the app configurations from a file, or creates one with default values if it doesn't exist.
@@ -84,11 +116,12 @@ fn dirs() -> Result<(PathBuf, PathBuf), DominionError> {
 /// On Linux systems, the file can be found on "/home/$USER/.config/dominion/dominion.toml".
 fn load_config(cfg_dir: PathBuf) -> Result<(Config, PathBuf), DominionConfigError> {
     let config_file = cfg_dir.join("dominion.toml");
-    let config =
+    let mut config =
         confy::load_path::<Config>(config_file.clone()).map_err(|e| DominionConfigError::Load {
             file: format!("{}", config_file.display()),
             source: e,
         })?;
+    config.resolve_secrets()?;
 
     Ok((config, config_file))
 }
@@ -114,19 +147,40 @@ fn init_log(cfg: &Config, default_log_dir: PathBuf) -> Result<WorkerGuard, Domin
 
     tracing_subscriber::fmt()
         .with_max_level(max_level)
+        .with_writer(Redacting(std::io::stdout))
         .finish()
         .with(targets)
-        .with(Layer::default().with_ansi(false).with_writer(file_writer))
+        .with(
+            Layer::default()
+                .with_ansi(false)
+                .with_writer(Redacting(file_writer)),
+        )
         .try_init()
         .map_err(DominionLogError::from)?;
 
     Ok(file_guard)
 }
 
-fn prepare_watcher(
+/// Prepares the store used to persist the watchers state between restarts.
+///
+/// On Linux systems, the data can be found on "/home/$USER/.local/share/dominion".
+fn init_store(cfg: &Config, default_data_dir: PathBuf) -> Result<Store, DominionError> {
+    let data_dir = cfg
+        .store
+        .dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or(default_data_dir);
+    debug!("Using data directory '{}'", data_dir.display());
+
+    Ok(Store::new(data_dir)?)
+}
+
+async fn prepare_watcher(
     entry: &WatchEntry,
     tx: Sender<NotificationEvent>,
     cfg: &Config,
+    store: Store,
 ) -> Result<(), DominionError> {
     let tx_spawn = tx;
     let tx_inner = tx_spawn.clone();
@@ -135,7 +189,7 @@ fn prepare_watcher(
     let stagger = Arc::new(entry.stagger);
     let variation = Arc::new(entry.variation);
 
-    let mut watcher = Watcher::new(entry, tx_inner, &cfg.http)?;
+    let mut watcher = Watcher::new(entry, tx_inner, &cfg.http, store).await?;
 
     tokio::spawn(async move {
         // Delay initial fetch by `stagger`
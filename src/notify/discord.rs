@@ -1,7 +1,6 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
-use reqwest::StatusCode;
 use serenity::builder::{CreateAttachment, CreateMessage, EditMessage, GetMessages};
 use serenity::http::Http;
 use serenity::model::channel::{Message, PrivateChannel};
@@ -11,7 +10,8 @@ use tracing::{debug, error, info, trace, warn};
 
 use crate::config::DiscordConfig;
 use crate::error::DominionDiscordError;
-use crate::notify::Heartbeat;
+use crate::notify::{attachments, Heartbeat};
+use crate::source::{BinaryContent, Status};
 
 pub struct DiscordEventHandler {
     http: Http,
@@ -20,6 +20,7 @@ pub struct DiscordEventHandler {
     status_msg: Option<Message>,
     purge: bool,
     purge_after: u64,
+    max_attachment_size: u64,
 }
 
 impl DiscordEventHandler {
@@ -45,6 +46,7 @@ impl DiscordEventHandler {
             status_msg: None,
             purge: cfg.purge,
             purge_after: cfg.purge_after,
+            max_attachment_size: cfg.max_attachment_size,
         })
     }
 
@@ -203,11 +205,126 @@ impl crate::notify::EventHandler for DiscordEventHandler {
         }
     }
 
+    async fn on_binary_changed(
+        &mut self,
+        url: &str,
+        old: Option<&BinaryContent>,
+        new: &BinaryContent,
+    ) {
+        let mut text = format!("Found changes in {url}\n");
+        if let Some(old) = old {
+            text += format!("Before:\n```\n{}```\n", old.describe()).as_str();
+        }
+        text += format!("After:\n```\n{}```", new.describe()).as_str();
+
+        let attachments = attachments(old, new, self.max_attachment_size);
+        if attachments.len() < 1 + usize::from(old.is_some()) {
+            text += "\nToo large to be attached.";
+        }
+
+        let mut msg = CreateMessage::new().content(text);
+        for (name, content) in attachments {
+            msg = msg.add_file(CreateAttachment::bytes(content.bytes.as_slice(), name));
+        }
+
+        match self.send(msg).await {
+            Err(err) => {
+                error!("Failed to send on change message in Discord: {err}");
+            }
+            _ => {
+                self.status_msg = None; // reset status message, so that a new one is sent in the next heartbeat
+            }
+        }
+    }
+
+    async fn on_new_item(
+        &mut self,
+        url: &str,
+        title: Option<&str>,
+        link: Option<&str>,
+        summary: Option<&str>,
+    ) {
+        let mut text = format!("New item in {url}\n**{}**", title.unwrap_or("(untitled)"));
+        if let Some(link) = link {
+            text += format!("\n<{link}>").as_str();
+        }
+        if let Some(summary) = summary {
+            let (summary_trimmed, summary_suffix, _) = DiscordEventHandler::trim(summary, 1500);
+            text += format!("\n>>> {summary_trimmed}{summary_suffix}").as_str();
+        }
+        let msg = CreateMessage::new().content(text);
+
+        match self.send(msg).await {
+            Err(err) => {
+                error!("Failed to send new item message in Discord: {err}");
+            }
+            _ => {
+                self.status_msg = None; // reset status message, so that a new one is sent in the next heartbeat
+            }
+        }
+    }
+
+    async fn on_sitemap_changed(
+        &mut self,
+        url: &str,
+        added: &[String],
+        removed: &[String],
+        modified: &[String],
+    ) {
+        let mut summary = String::new();
+        for (title, urls) in [
+            ("Added", added),
+            ("Removed", removed),
+            ("Modified", modified),
+        ] {
+            if !urls.is_empty() {
+                summary += format!("{title} ({}):\n", urls.len()).as_str();
+                for url in urls {
+                    summary += format!("- {url}\n").as_str();
+                }
+            }
+        }
+
+        // Truncate summary as to not exceed Discord limit of 2000 characters per message
+        let (summary_trimmed, summary_suffix, was_trimmed) =
+            DiscordEventHandler::trim(summary.as_str(), 1800);
+
+        let text = format!("Found changes in {url}\n```\n{summary_trimmed}{summary_suffix}```");
+        let mut msg = CreateMessage::new().content(text);
+
+        if was_trimmed {
+            msg = msg.add_file(CreateAttachment::bytes(summary.as_bytes(), "summary.txt"));
+        }
+
+        match self.send(msg).await {
+            Err(err) => {
+                error!("Failed to send sitemap changes message in Discord: {err}");
+            }
+            _ => {
+                self.status_msg = None; // reset status message, so that a new one is sent in the next heartbeat
+            }
+        }
+    }
+
+    async fn on_warning(&mut self, url: &str, message: &str) {
+        let text = format!("Warning for {url}:\n```\n{message}\n```");
+        let msg = CreateMessage::new().content(text);
+
+        match self.send(msg).await {
+            Err(err) => {
+                error!("Failed to send warning message in Discord: {err}");
+            }
+            _ => {
+                self.status_msg = None; // reset status message, so that a new one is sent in the next heartbeat
+            }
+        }
+    }
+
     async fn on_failed(
         &mut self,
         url: &str,
         reason: &str,
-        status: &Option<StatusCode>,
+        status: &Option<Status>,
         body: &Option<String>,
     ) {
         let mut msg = CreateMessage::new();
@@ -1,10 +1,10 @@
 use async_trait::async_trait;
-use handlebars::{no_escape, Handlebars};
-use lettre::message::{Mailbox, SinglePart};
+use handlebars::{html_escape, no_escape, Handlebars};
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::transport::smtp::client::{Tls, TlsParameters};
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
-use reqwest::StatusCode;
 use serde::Serialize;
 use serde_json::json;
 use similar::{ChangeTag, TextDiff};
@@ -12,7 +12,8 @@ use tracing::{error, trace};
 
 use crate::config::MailConfig;
 use crate::error::DominionMailError;
-use crate::notify::{EventHandler, Heartbeat};
+use crate::notify::{attachments, EventHandler, Heartbeat};
+use crate::source::{BinaryContent, Status};
 
 #[derive(Serialize)]
 struct CodeBlock {
@@ -33,6 +34,7 @@ pub struct MailEventHandler<'te> {
     template_engine: Handlebars<'te>,
     from_addr: Mailbox,
     to_addr: Mailbox,
+    max_attachment_size: u64,
 }
 
 impl<'te> MailEventHandler<'te> {
@@ -42,6 +44,7 @@ impl<'te> MailEventHandler<'te> {
             template_engine: create_template_engine()?,
             from_addr: cfg.from_address.as_str().parse()?,
             to_addr: cfg.to_address.as_str().parse()?,
+            max_attachment_size: cfg.max_attachment_size,
         })
     }
 
@@ -49,13 +52,39 @@ impl<'te> MailEventHandler<'te> {
         &self,
         subject: S,
         data: serde_json::Value,
+    ) -> Result<(), DominionMailError> {
+        self.send_mail_with_attachments(subject, data, &[]).await
+    }
+
+    /// Sends a mail with files attached, given by name.
+    async fn send_mail_with_attachments<S: Into<String>>(
+        &self,
+        subject: S,
+        data: serde_json::Value,
+        attachments: &[(String, &BinaryContent)],
     ) -> Result<(), DominionMailError> {
         let body = self.template_engine.render("template", &data)?;
-        let mail = Message::builder()
+        let builder = Message::builder()
             .from(self.from_addr.clone())
             .to(self.to_addr.clone())
-            .subject(subject)
-            .singlepart(SinglePart::html(body))?;
+            .subject(subject);
+
+        let mail = if attachments.is_empty() {
+            builder.singlepart(SinglePart::html(body))?
+        } else {
+            let mut multipart = MultiPart::mixed().singlepart(SinglePart::html(body));
+            for (name, content) in attachments {
+                let content_type = content
+                    .content_type
+                    .as_deref()
+                    .and_then(|content_type| ContentType::parse(content_type).ok())
+                    .unwrap_or_else(|| ContentType::parse("application/octet-stream").unwrap());
+                multipart = multipart.singlepart(
+                    Attachment::new(name.clone()).body(content.bytes.clone(), content_type),
+                );
+            }
+            builder.multipart(multipart)?
+        };
 
         self.mailer.send(mail).await?;
 
@@ -154,11 +183,121 @@ impl<'te> EventHandler for MailEventHandler<'te> {
         }
     }
 
+    async fn on_binary_changed(
+        &mut self,
+        url: &str,
+        old: Option<&BinaryContent>,
+        new: &BinaryContent,
+    ) {
+        let mut content = format!(
+            r#"<p>The following changes were found in <a target="_blank" href="{url}">{url}</a></p>"#
+        );
+        if let Some(old) = old {
+            content += format!("<p>Before:</p><pre>{}</pre>", old.describe()).as_str();
+        }
+        content += format!("<p>After:</p><pre>{}</pre>", new.describe()).as_str();
+
+        let attachments = attachments(old, new, self.max_attachment_size);
+        if attachments.len() < 1 + usize::from(old.is_some()) {
+            content += "<p>Too large to be attached.</p>";
+        }
+
+        let subject = format!("Changes in {}", url);
+        let body = json!({ "content": content });
+
+        let result = self
+            .send_mail_with_attachments(subject, body, attachments.as_slice())
+            .await;
+        match result {
+            Ok(_) => trace!("Email for changes in {url} sent"),
+            Err(err) => error!("Failed to send email for changes in {url}: {err}"),
+        }
+    }
+
+    async fn on_new_item(
+        &mut self,
+        url: &str,
+        title: Option<&str>,
+        link: Option<&str>,
+        summary: Option<&str>,
+    ) {
+        // The item comes from a third party, so it must not be able to inject any HTML
+        let title = title.unwrap_or("(untitled)");
+        let escaped_title = html_escape(title);
+        let mut content = match link.map(html_escape) {
+            Some(link) => {
+                format!(r#"<p><a target="_blank" href="{link}">{escaped_title}</a></p>"#)
+            }
+            None => format!("<p>{escaped_title}</p>"),
+        };
+        if let Some(summary) = summary {
+            content += format!("<p>{}</p>", html_escape(summary)).as_str();
+        }
+        content += format!(r#"<p>Found in <a target="_blank" href="{url}">{url}</a></p>"#).as_str();
+
+        let subject = format!("New item in {url}: {title}");
+        let body = json!({ "content": content });
+
+        let result = self.send_mail(subject, body).await;
+        match result {
+            Ok(_) => trace!("Email for new item in {url} sent"),
+            Err(err) => error!("Failed to send email for new item in {url}: {err}"),
+        }
+    }
+
+    async fn on_sitemap_changed(
+        &mut self,
+        url: &str,
+        added: &[String],
+        removed: &[String],
+        modified: &[String],
+    ) {
+        let mut content = format!(
+            r#"<p>The following changes were found in <a target="_blank" href="{url}">{url}</a></p>"#
+        );
+        for (title, urls) in [
+            ("Added", added),
+            ("Removed", removed),
+            ("Modified", modified),
+        ] {
+            if !urls.is_empty() {
+                let mut urls_joined = String::new();
+                for url in urls.iter().map(|url| html_escape(url)) {
+                    urls_joined +=
+                        format!(r#"<li><a target="_blank" href="{url}">{url}</a></li>"#).as_str();
+                }
+                content +=
+                    format!("<p>{title} ({}):</p><ul>{urls_joined}</ul>", urls.len()).as_str();
+            }
+        }
+
+        let subject = format!("Changes in {}", url);
+        let body = json!({ "content": content });
+
+        let result = self.send_mail(subject, body).await;
+        match result {
+            Ok(_) => trace!("Email for changes in {url} sent"),
+            Err(err) => error!("Failed to send email for changes in {url}: {err}"),
+        }
+    }
+
+    async fn on_warning(&mut self, url: &str, message: &str) {
+        let content = format!("<p>Warning for {url}</p><p>{message}</p>");
+
+        let subject = format!("Warning for {url}");
+        let body = json!({ "content": content });
+
+        let result = self.send_mail(subject, body).await;
+        if let Err(err) = result {
+            error!("Failed to send warning email: {err}");
+        }
+    }
+
     async fn on_failed(
         &mut self,
         url: &str,
         reason: &str,
-        _status: &Option<StatusCode>,
+        _status: &Option<Status>,
         _body: &Option<String>,
     ) {
         let content = format!("<p>Failed to fetch {url}</p><p>{reason}</p>");
@@ -3,16 +3,17 @@ use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
-use reqwest::StatusCode;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::{Mutex, RwLock};
 use tokio::time::Instant;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 use crate::config::{Config, WatchEntry};
 use crate::error::DominionError;
 use crate::notify::discord::DiscordEventHandler;
 use crate::notify::mail::MailEventHandler;
+use crate::redact;
+use crate::source::{BinaryContent, Status};
 use crate::NotificationEvent;
 
 #[cfg(feature = "discord")]
@@ -24,11 +25,32 @@ mod mail;
 trait EventHandler {
     async fn on_startup(&mut self, urls: &[String]);
     async fn on_changed(&mut self, url: &str, old: &str, new: &str);
+    async fn on_binary_changed(
+        &mut self,
+        url: &str,
+        old: Option<&BinaryContent>,
+        new: &BinaryContent,
+    );
+    async fn on_new_item(
+        &mut self,
+        url: &str,
+        title: Option<&str>,
+        link: Option<&str>,
+        summary: Option<&str>,
+    );
+    async fn on_sitemap_changed(
+        &mut self,
+        url: &str,
+        added: &[String],
+        removed: &[String],
+        modified: &[String],
+    );
+    async fn on_warning(&mut self, url: &str, message: &str);
     async fn on_failed(
         &mut self,
         url: &str,
         reason: &str,
-        status: &Option<StatusCode>,
+        status: &Option<Status>,
         body: &Option<String>,
     );
     async fn on_heartbeat(&mut self, status: &Heartbeat);
@@ -48,7 +70,7 @@ impl Heartbeat {
 
         entries
             .iter()
-            .map(|w| HeartbeatItem::new(w.url.as_str()))
+            .map(|w| HeartbeatItem::new(redact::redact(w.url.as_str()).as_str()))
             .for_each(|e| heartbeat.items.push(e));
 
         heartbeat
@@ -138,7 +160,7 @@ pub async fn prepare_notifier(cfg: &Config) -> Result<Sender<NotificationEvent>,
         let heartbeat = heartbeat.clone();
         tokio::spawn(async move {
             while let Some(message) = rx.recv().await {
-                match message {
+                match redact_event(message) {
                     NotificationEvent::Startup { urls } => {
                         let urls = urls.as_slice();
 
@@ -165,10 +187,98 @@ pub async fn prepare_notifier(cfg: &Config) -> Result<Sender<NotificationEvent>,
                             mail.lock().await.on_changed(url, old, new).await;
                         }
                     }
+                    NotificationEvent::BinaryChanged { url, old, new } => {
+                        info!("Found changes in {url}");
+
+                        let url = url.as_str();
+                        let old = old.as_deref();
+
+                        update_heartbeat(&heartbeat, url, HeartbeatType::Change).await;
+
+                        if let Some(discord) = discord_handler.deref() {
+                            discord.lock().await.on_binary_changed(url, old, &new).await;
+                        }
+                        if let Some(mail) = mail_handler.deref() {
+                            mail.lock().await.on_binary_changed(url, old, &new).await;
+                        }
+                    }
+                    NotificationEvent::NewItem {
+                        url,
+                        title,
+                        link,
+                        summary,
+                    } => {
+                        info!("Found new item in {url}: {title:?}");
+
+                        let url = url.as_str();
+                        let title = title.as_deref();
+                        let link = link.as_deref();
+                        let summary = summary.as_deref();
+
+                        update_heartbeat(&heartbeat, url, HeartbeatType::Change).await;
+
+                        if let Some(discord) = discord_handler.deref() {
+                            discord
+                                .lock()
+                                .await
+                                .on_new_item(url, title, link, summary)
+                                .await;
+                        }
+                        if let Some(mail) = mail_handler.deref() {
+                            mail.lock()
+                                .await
+                                .on_new_item(url, title, link, summary)
+                                .await;
+                        }
+                    }
+                    NotificationEvent::SitemapChanged {
+                        url,
+                        added,
+                        removed,
+                        modified,
+                    } => {
+                        info!(
+                            "Found changes in {url}: {} added, {} removed, {} modified",
+                            added.len(),
+                            removed.len(),
+                            modified.len()
+                        );
+
+                        let url = url.as_str();
+
+                        update_heartbeat(&heartbeat, url, HeartbeatType::Change).await;
+
+                        if let Some(discord) = discord_handler.deref() {
+                            discord
+                                .lock()
+                                .await
+                                .on_sitemap_changed(url, &added, &removed, &modified)
+                                .await;
+                        }
+                        if let Some(mail) = mail_handler.deref() {
+                            mail.lock()
+                                .await
+                                .on_sitemap_changed(url, &added, &removed, &modified)
+                                .await;
+                        }
+                    }
                     NotificationEvent::NoChanges { url } => {
                         update_heartbeat(&heartbeat, url.as_str(), HeartbeatType::NoChange).await;
                         do_heartbeat(&heartbeat, &discord_handler, &mail_handler).await;
                     }
+                    NotificationEvent::Warning { url, message } => {
+                        warn!("Warning for {url}: {message}");
+
+                        let url = url.as_str();
+                        let message = message.as_str();
+
+                        if let Some(discord) = discord_handler.deref() {
+                            discord.lock().await.on_warning(url, message).await;
+                        }
+                        if let Some(mail) = mail_handler.deref() {
+                            mail.lock().await.on_warning(url, message).await;
+                        }
+                    }
                     NotificationEvent::Failed {
                         url,
                         reason,
@@ -232,6 +342,87 @@ pub async fn prepare_notifier(cfg: &Config) -> Result<Sender<NotificationEvent>,
     Ok(tx)
 }
 
+/// Masks the secrets in the URL and text of an event, e.g. credentials in a query string or
+/// echoed back by a failed request. The content of binary files is kept as is.
+fn redact_event(event: NotificationEvent) -> NotificationEvent {
+    let redact = |text: String| redact::redact(text.as_str());
+    let redact_all = |texts: Vec<String>| texts.into_iter().map(redact).collect();
+
+    match event {
+        NotificationEvent::Startup { urls } => NotificationEvent::Startup {
+            urls: redact_all(urls),
+        },
+        NotificationEvent::Changed { url, old, new } => NotificationEvent::Changed {
+            url: redact(url),
+            old: redact(old),
+            new: redact(new),
+        },
+        NotificationEvent::BinaryChanged { url, old, new } => NotificationEvent::BinaryChanged {
+            url: redact(url),
+            old,
+            new,
+        },
+        NotificationEvent::NewItem {
+            url,
+            title,
+            link,
+            summary,
+        } => NotificationEvent::NewItem {
+            url: redact(url),
+            title: title.map(redact),
+            link: link.map(redact),
+            summary: summary.map(redact),
+        },
+        NotificationEvent::SitemapChanged {
+            url,
+            added,
+            removed,
+            modified,
+        } => NotificationEvent::SitemapChanged {
+            url: redact(url),
+            added: redact_all(added),
+            removed: redact_all(removed),
+            modified: redact_all(modified),
+        },
+        NotificationEvent::NoChanges { url } => NotificationEvent::NoChanges { url: redact(url) },
+        NotificationEvent::Warning { url, message } => NotificationEvent::Warning {
+            url: redact(url),
+            message: redact(message),
+        },
+        NotificationEvent::Failed {
+            url,
+            reason,
+            status,
+            body,
+        } => NotificationEvent::Failed {
+            url: redact(url),
+            reason: redact(reason),
+            status,
+            body: body.map(redact),
+        },
+    }
+}
+
+/// Picks the contents of a binary change to attach to a notification, the new one first, as long
+/// as they fit in the maximum size. Those left out are only described.
+fn attachments<'a>(
+    old: Option<&'a BinaryContent>,
+    new: &'a BinaryContent,
+    max_size: u64,
+) -> Vec<(String, &'a BinaryContent)> {
+    let mut attachments = vec![];
+    let mut size = 0;
+    for (name, content) in [("new", Some(new)), ("old", old)] {
+        if let Some(content) = content
+            && size + content.bytes.len() as u64 <= max_size
+        {
+            size += content.bytes.len() as u64;
+            attachments.push((format!("{name}.{}", content.extension()), content));
+        }
+    }
+    attachments
+}
+
 async fn update_heartbeat(heartbeat: &RwLock<Heartbeat>, url: &str, update_type: HeartbeatType) {
     heartbeat.write().await.update(url, update_type);
 }
@@ -251,3 +442,27 @@ async fn do_heartbeat<'te>(
         mail.lock().await.on_heartbeat(&heartbeat_guard).await;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::notify::*;
+
+    #[test]
+    fn attachment_size() {
+        let old = BinaryContent::new(vec![0; 6], Some("image/png".to_string()));
+        let new = BinaryContent::new(vec![0; 4], Some("application/pdf".to_string()));
+
+        let names = |attachments: Vec<(String, &BinaryContent)>| {
+            attachments
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(attachments(Some(&old), &new, 10)),
+            ["new.pdf", "old.png"]
+        );
+        assert_eq!(names(attachments(Some(&old), &new, 9)), ["new.pdf"]);
+        assert!(attachments(Some(&old), &new, 3).is_empty());
+    }
+}
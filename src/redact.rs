@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::sync::RwLock;
+
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::{AuthConfig, Config};
+
+const MASK: &str = "[redacted]";
+
+/// The secrets of the configuration, masked in all logs and notifications.
+static SECRETS: Secrets = Secrets::new();
+
+/// Known secret values, longest first so that secrets containing others are fully masked.
+pub struct Secrets(RwLock<Vec<String>>);
+
+impl Secrets {
+    pub const fn new() -> Self {
+        Self(RwLock::new(Vec::new()))
+    }
+
+    pub fn register(&self, secret: &str) {
+        if secret.is_empty() {
+            return;
+        }
+
+        let mut secrets = self.0.write().expect("secrets lock poisoned");
+        if !secrets.iter().any(|s| s == secret) {
+            secrets.push(secret.to_string());
+            secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let secrets = self.0.read().expect("secrets lock poisoned");
+        let mut text = text.to_string();
+        for secret in secrets.iter() {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), MASK);
+            }
+        }
+        text
+    }
+}
+
+/// Marks a value as secret, so that it is masked in logs and notifications.
+pub fn register(secret: &str) {
+    SECRETS.register(secret);
+}
+
+/// Registers the secrets present in the configuration: credentials, and the values of headers
+/// deemed sensitive.
+pub fn register_config(cfg: &Config) {
+    #[cfg(feature = "discord")]
+    register(cfg.discord.token.as_str());
+    #[cfg(feature = "email")]
+    register(cfg.email.smtp_password.as_str());
+
+    for entry in &cfg.watch {
+        entry
+            .headers
+            .iter()
+            .filter_map(|header| sensitive_value(header, &cfg.redact.headers))
+            .for_each(register);
+
+        match &entry.auth {
+            Some(AuthConfig::Basic {
+                password: Some(password),
+                ..
+            }) => register(password),
+            Some(AuthConfig::Bearer { token }) => register(token),
+            Some(AuthConfig::OAuth2 { client_secret, .. }) => register(client_secret),
+            _ => {}
+        }
+
+        if let Some(session) = &entry.session {
+            session
+                .steps
+                .iter()
+                .flat_map(|step| step.headers.iter())
+                .filter_map(|header| sensitive_value(header, &cfg.redact.headers))
+                .for_each(register);
+        }
+    }
+}
+
+/// Gets the value of a 'name=value' header, if the header is among the sensitive ones.
+fn sensitive_value<'a>(header: &'a str, sensitive: &[String]) -> Option<&'a str> {
+    let (name, value) = header.split_once('=')?;
+    sensitive
+        .iter()
+        .any(|redacted| redacted.eq_ignore_ascii_case(name.trim()))
+        .then_some(value)
+}
+
+/// Masks all known secrets in a text.
+pub fn redact(text: &str) -> String {
+    SECRETS.redact(text)
+}
+
+/// Wraps the writers of a tracing layer, masking the secrets of each event before it is written.
+pub struct Redacting<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer(), &SECRETS)
+    }
+}
+
+pub struct RedactingWriter<W>(W, &'static Secrets);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Events are formatted in full before being written, so secrets are never split
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(self.1.redact(text.as_ref()).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::redact::*;
+
+    #[test]
+    fn mask() {
+        // Not the global secrets, which other tests register theirs in
+        static SECRETS: Secrets = Secrets::new();
+        SECRETS.register("s3cr3t");
+        SECRETS.register("s3cr3t-and-more");
+
+        assert_eq!(
+            SECRETS.redact("token=s3cr3t-and-more, password=s3cr3t"),
+            "token=[redacted], password=[redacted]"
+        );
+
+        let mut output = vec![];
+        let mut writer = RedactingWriter(&mut output, &SECRETS);
+        writer.write_all(b"Authorization: Bearer s3cr3t\n").unwrap();
+        assert_eq!(output, b"Authorization: Bearer [redacted]\n");
+    }
+}
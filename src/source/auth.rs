@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::config::AuthConfig;
+use crate::error::DominionRequestError;
+use crate::redact;
+
+/// How long before expiring should a token be renewed, to account for clock skew and slow requests.
+const TOKEN_RENEWAL_MARGIN: Duration = Duration::from_secs(30);
+
+/// Assumed lifetime of tokens issued without an expiry.
+const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Adds credentials to the requests of an [HttpSource](crate::source::http::HttpSource).
+#[derive(Debug)]
+pub enum Auth {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer {
+        token: String,
+    },
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+        token: Mutex<Option<AccessToken>>,
+    },
+}
+
+#[derive(Debug)]
+pub struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl Auth {
+    pub fn new(cfg: &AuthConfig) -> Self {
+        match cfg.clone() {
+            AuthConfig::Basic { username, password } => Auth::Basic { username, password },
+            AuthConfig::Bearer { token } => Auth::Bearer { token },
+            AuthConfig::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scopes,
+            } => Auth::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scopes,
+                token: Mutex::new(None),
+            },
+        }
+    }
+
+    pub async fn apply(
+        &self,
+        req: RequestBuilder,
+        client: &Client,
+    ) -> Result<RequestBuilder, DominionRequestError> {
+        let req = match self {
+            Auth::Basic { username, password } => req.basic_auth(username, password.as_ref()),
+            Auth::Bearer { token } => req.bearer_auth(token),
+            Auth::OAuth2 { .. } => req.bearer_auth(self.access_token(client).await?),
+        };
+        Ok(req)
+    }
+
+    /// Forgets the cached token, e.g. after it was rejected, so that a new one is requested.
+    pub async fn invalidate(&self) {
+        if let Auth::OAuth2 { token, .. } = self {
+            *token.lock().await = None;
+        }
+    }
+
+    /// Returns the cached OAuth2 access token, requesting a new one if it is about to expire.
+    async fn access_token(&self, client: &Client) -> Result<String, DominionRequestError> {
+        let Auth::OAuth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scopes,
+            token,
+        } = self
+        else {
+            unreachable!("only OAuth2 uses access tokens");
+        };
+
+        let mut token = token.lock().await;
+        if let Some(token) = token.as_ref()
+            && Instant::now() + TOKEN_RENEWAL_MARGIN < token.expires_at
+        {
+            return Ok(token.value.clone());
+        }
+
+        debug!("Requesting access token from {token_url}");
+        let mut form = vec![("grant_type", "client_credentials".to_string())];
+        if !scopes.is_empty() {
+            form.push(("scope", scopes.join(" ")));
+        }
+
+        let requested_at = Instant::now();
+        let res = client
+            .post(token_url.as_str())
+            .basic_auth(client_id, Some(client_secret))
+            .form(&form)
+            .send()
+            .await?;
+
+        let status = res.status();
+        if !status.is_success() {
+            return Err(DominionRequestError::HttpRequestFailed {
+                url: token_url.clone(),
+                status,
+                body: res.text().await?,
+            });
+        }
+
+        let response = serde_json::from_str::<TokenResponse>(res.text().await?.as_str())?;
+        let lifetime = response
+            .expires_in
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TOKEN_LIFETIME);
+
+        redact::register(response.access_token.as_str());
+        *token = Some(AccessToken {
+            value: response.access_token.clone(),
+            expires_at: requested_at + lifetime,
+        });
+        Ok(response.access_token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::source::auth::*;
+    use crate::source::mock;
+
+    /// Serves tokens that are about to expire on odd requests, and long-lived ones on even requests.
+    async fn mock_token_endpoint(requests: Arc<AtomicUsize>) -> String {
+        let server = mock::serve(move |request| {
+            assert!(request.starts_with("POST /token"));
+            assert!(request.contains("grant_type=client_credentials"));
+
+            let n = requests.fetch_add(1, Ordering::SeqCst) + 1;
+            let expires_in = if n % 2 == 1 { 1 } else { 3600 };
+            let body = format!(
+                r#"{{"access_token":"token-{n}","token_type":"Bearer","expires_in":{expires_in}}}"#
+            );
+            Some(mock::response(
+                "200 OK",
+                &[("Content-Type", "application/json")],
+                body.as_str(),
+            ))
+        })
+        .await;
+
+        format!("{server}/token")
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_refresh() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let token_url = mock_token_endpoint(requests.clone()).await;
+
+        let auth = Auth::new(&AuthConfig::OAuth2 {
+            token_url,
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: vec!["read".to_string()],
+        });
+        let client = Client::new();
+
+        // The first token expires within the renewal margin, so it is immediately renewed
+        assert_eq!(auth.access_token(&client).await.unwrap(), "token-1");
+        assert_eq!(auth.access_token(&client).await.unwrap(), "token-2");
+        assert_eq!(auth.access_token(&client).await.unwrap(), "token-2");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        auth.invalidate().await;
+        assert_eq!(auth.access_token(&client).await.unwrap(), "token-3");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,121 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+use tracing::trace;
+
+use crate::config::{format_duration, parse_pair, WatchEntry};
+use crate::error::{DominionConfigError, DominionRequestError};
+use crate::source::{Response, Source, Status};
+
+/// Runs a program and watches its standard output.
+#[derive(Debug)]
+pub struct CommandSource {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    timeout: Duration,
+}
+
+impl CommandSource {
+    pub fn new(entry: &WatchEntry) -> Result<Self, DominionConfigError> {
+        let env = entry
+            .env
+            .iter()
+            .map(|e| parse_pair(e, "environment variable", "name=value"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            program: entry.url.clone(),
+            args: entry.args.clone(),
+            env,
+            timeout: entry.timeout,
+        })
+    }
+
+    fn command_line(&self) -> String {
+        let mut line = self.program.clone();
+        for arg in &self.args {
+            line += " ";
+            line += arg;
+        }
+        line
+    }
+}
+
+#[async_trait]
+impl Source for CommandSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let child = Command::new(&self.program)
+            .args(&self.args)
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| DominionRequestError::CommandSpawn {
+                command: self.command_line(),
+                source: e,
+            })?;
+
+        trace!("Running '{}'", self.command_line());
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_elapsed| DominionRequestError::CommandTimeout {
+                command: self.command_line(),
+                timeout: format_duration(&self.timeout),
+            })?
+            .map_err(|e| DominionRequestError::CommandSpawn {
+                command: self.command_line(),
+                source: e,
+            })?;
+        trace!("Ran '{}': {:?}", self.command_line(), output.status);
+
+        if !output.status.success() {
+            return Err(DominionRequestError::CommandFailed {
+                command: self.command_line(),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+
+        Ok(Response {
+            content: String::from_utf8_lossy(&output.stdout).to_string(),
+            status: Some(Status::Exit(output.status)),
+            metadata: vec![],
+            warnings: vec![],
+            binary: None,
+            elapsed: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::command::*;
+
+    #[tokio::test]
+    async fn non_zero_exit() {
+        let entry = WatchEntry {
+            url: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "echo $GREETING; echo oops >&2; exit 3".to_string(),
+            ],
+            env: vec!["GREETING=hello".to_string()],
+            timeout: Duration::from_secs(5),
+            ..WatchEntry::default()
+        };
+
+        let result = CommandSource::new(&entry).unwrap().fetch().await;
+        match result {
+            Err(DominionRequestError::CommandFailed { status, stderr, .. }) => {
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "oops\n");
+            }
+            other => panic!("expected command failure, got {other:?}"),
+        }
+    }
+}
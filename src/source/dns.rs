@@ -0,0 +1,186 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig};
+use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::proto::rr::RecordType;
+use hickory_resolver::TokioResolver;
+use tracing::trace;
+
+use crate::config::WatchEntry;
+use crate::error::{DominionConfigError, DominionError, DominionRequestError};
+use crate::source::{Response, Source};
+
+const DEFAULT_DNS_PORT: u16 = 53;
+
+/// Resolves the records of a domain name.
+#[derive(Debug)]
+pub struct DnsSource {
+    name: String,
+    record_types: Vec<RecordType>,
+    resolver: TokioResolver,
+}
+
+impl DnsSource {
+    pub fn new(entry: &WatchEntry) -> Result<Self, DominionError> {
+        let name = entry.url.strip_prefix("dns://").unwrap_or(&entry.url);
+
+        let record_types = entry
+            .records
+            .iter()
+            .map(|record| {
+                RecordType::from_str(record.to_uppercase().as_str()).map_err(|_err| {
+                    DominionConfigError::UnknownRecordType {
+                        url: entry.url.clone(),
+                        record: record.clone(),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut builder = match &entry.resolver {
+            Some(resolver) => {
+                let address = parse_resolver(resolver)?;
+                let name_servers =
+                    NameServerConfigGroup::from_ips_clear(&[address.ip()], address.port(), true);
+                let config = ResolverConfig::from_parts(None, vec![], name_servers);
+                TokioResolver::builder_with_config(config, TokioConnectionProvider::default())
+            }
+            None => TokioResolver::builder_tokio().map_err(DominionRequestError::from)?,
+        };
+
+        // Always ask the name servers, or else changes would only be seen after the TTL expires
+        builder.options_mut().cache_size = 0;
+        builder.options_mut().timeout = entry.timeout;
+
+        Ok(Self {
+            name: name.to_string(),
+            record_types,
+            resolver: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Source for DnsSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let mut records = vec![];
+
+        for record_type in &self.record_types {
+            trace!("Resolving {record_type} records of {}", self.name);
+            match self.resolver.lookup(self.name.as_str(), *record_type).await {
+                Ok(lookup) => {
+                    for record in lookup.record_iter() {
+                        if record.record_type() == *record_type {
+                            records.push(format!("{record_type}\t{}", record.data()));
+                        }
+                    }
+                }
+                // A missing record is a valid state to watch
+                Err(err) if err.is_no_records_found() => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        records.sort();
+
+        Ok(Response {
+            content: records.join("\n"),
+            status: None,
+            metadata: vec![],
+            warnings: vec![],
+            binary: None,
+            elapsed: None,
+        })
+    }
+}
+
+/// Parses the address of a name server, in the format 'ip' or 'ip:port'.
+fn parse_resolver(resolver: &str) -> Result<SocketAddr, DominionRequestError> {
+    if let Ok(address) = SocketAddr::from_str(resolver) {
+        return Ok(address);
+    }
+    IpAddr::from_str(resolver)
+        .map(|ip| SocketAddr::new(ip, DEFAULT_DNS_PORT))
+        .map_err(|_err| DominionRequestError::InvalidAddress {
+            address: resolver.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use hickory_resolver::proto::op::{Message, MessageType, ResponseCode};
+    use hickory_resolver::proto::rr::rdata::{A, MX};
+    use hickory_resolver::proto::rr::{Name, RData, Record};
+    use tokio::net::UdpSocket;
+
+    use crate::source::dns::*;
+
+    /// Answers every query with a fixed set of records.
+    async fn stub_server() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                let query = request.queries()[0].clone();
+                let name = query.name().clone();
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(request.op_code())
+                    .set_recursion_desired(request.recursion_desired())
+                    .set_response_code(ResponseCode::NoError)
+                    .add_query(query.clone());
+
+                match query.query_type() {
+                    RecordType::A => {
+                        for ip in [[192, 0, 2, 2], [192, 0, 2, 1]] {
+                            let rdata = RData::A(A(Ipv4Addr::from(ip)));
+                            response.add_answer(Record::from_rdata(name.clone(), 60, rdata));
+                        }
+                    }
+                    RecordType::MX => {
+                        let exchange = Name::from_str("mail.example.com.").unwrap();
+                        let rdata = RData::MX(MX::new(10, exchange));
+                        response.add_answer(Record::from_rdata(name.clone(), 60, rdata));
+                    }
+                    _ => {}
+                }
+
+                let bytes = response.to_vec().unwrap();
+                socket.send_to(&bytes, peer).await.unwrap();
+            }
+        });
+
+        address
+    }
+
+    #[tokio::test]
+    async fn records() {
+        let address = stub_server().await;
+        let entry = WatchEntry {
+            protocol: "dns".to_string(),
+            url: "example.com.".to_string(),
+            records: vec!["a".to_string(), "MX".to_string(), "TXT".to_string()],
+            resolver: Some(address.to_string()),
+            timeout: Duration::from_secs(5),
+            ..WatchEntry::default()
+        };
+
+        let response = DnsSource::new(&entry).unwrap().fetch().await.unwrap();
+        assert_eq!(
+            response.content,
+            "A\t192.0.2.1\nA\t192.0.2.2\nMX\t10 mail.example.com."
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use feed_rs::model::{Content, Entry};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{HttpConfig, WatchEntry};
+use crate::error::{DominionError, DominionRequestError};
+use crate::source::http::HttpSource;
+use crate::source::{Response, Source};
+use crate::transform::{json, text};
+use crate::NotificationEvent;
+
+/// Fetches an RSS or Atom feed over HTTP(S), and lists its items.
+///
+/// New items are reported one by one, identified by their GUID, instead of as a diff of the feed.
+///
+/// The GUIDs already seen are remembered across restarts, so that an item that is bumped or briefly
+/// drops out of the feed is not reported again. The most recent [MAX_SEEN_ITEMS] are kept, or all
+/// those of the feed if it is longer.
+#[derive(Debug)]
+pub struct FeedSource {
+    http: HttpSource,
+    /// GUIDs of the items already seen, the most recently seen last.
+    seen: VecDeque<String>,
+    /// GUIDs of the items last fetched, seen once committed.
+    fetched: Vec<String>,
+}
+
+const MAX_SEEN_ITEMS: usize = 1000;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FeedItem {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+impl FeedSource {
+    pub fn new(entry: &WatchEntry, http_cfg: &HttpConfig) -> Result<Self, DominionError> {
+        Ok(Self {
+            http: HttpSource::new(entry, http_cfg)?,
+            seen: VecDeque::new(),
+            fetched: vec![],
+        })
+    }
+}
+
+#[async_trait]
+impl Source for FeedSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let response = self.http.fetch().await?;
+        if response.is_not_modified() {
+            return Ok(response);
+        }
+
+        let items = parse_items(response.content.as_bytes())?;
+        self.fetched = items.iter().map(|item| item.id.clone()).collect();
+        let value = serde_json::to_value(items)?;
+
+        Ok(Response {
+            content: json::pretty(&value)?,
+            status: response.status,
+            metadata: vec![("content-type".to_string(), "application/json".to_string())],
+            warnings: response.warnings,
+            binary: None,
+            elapsed: response.elapsed,
+        })
+    }
+
+    fn commit(&mut self) {
+        self.http.commit();
+
+        let fetched = std::mem::take(&mut self.fetched);
+        let max = MAX_SEEN_ITEMS.max(fetched.len());
+        self.seen.retain(|id| !fetched.contains(id));
+        self.seen.extend(fetched);
+        while self.seen.len() > max {
+            self.seen.pop_front();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.http.reset();
+    }
+
+    fn state(&self) -> Option<String> {
+        serde_json::to_string(&self.seen).ok()
+    }
+
+    fn restore(&mut self, state: &str) -> Result<(), DominionRequestError> {
+        self.seen = serde_json::from_str(state)?;
+        Ok(())
+    }
+
+    fn changes(&self, url: &str, old: &str, new: &str) -> Vec<NotificationEvent> {
+        let (Ok(old_items), Ok(new_items)) = (
+            serde_json::from_str::<Vec<FeedItem>>(old),
+            serde_json::from_str::<Vec<FeedItem>>(new),
+        ) else {
+            // The items were reshaped by the transformations, so fall back to a plain diff
+            return vec![NotificationEvent::Changed {
+                url: url.to_string(),
+                old: old.to_string(),
+                new: new.to_string(),
+            }];
+        };
+
+        new_items
+            .into_iter()
+            .filter(|item| !old_items.iter().any(|old_item| old_item.id == item.id))
+            .filter(|item| !self.seen.contains(&item.id))
+            .map(|item| NotificationEvent::NewItem {
+                url: url.to_string(),
+                title: item.title,
+                link: item.link,
+                summary: item.summary,
+            })
+            .collect()
+    }
+}
+
+fn parse_items(content: &[u8]) -> Result<Vec<FeedItem>, DominionRequestError> {
+    let feed = feed_rs::parser::parse(content)?;
+    feed.entries.iter().map(FeedItem::from_entry).collect()
+}
+
+impl FeedItem {
+    fn from_entry(entry: &Entry) -> Result<Self, DominionRequestError> {
+        let summary = match (&entry.summary, &entry.content) {
+            (Some(summary), _) => Some(plain_text(
+                &summary.content,
+                is_html(summary.content_type.subty().as_str()),
+            )?),
+            (
+                None,
+                Some(Content {
+                    body: Some(body),
+                    content_type,
+                    ..
+                }),
+            ) => Some(plain_text(body, is_html(content_type.subty().as_str()))?),
+            _ => None,
+        };
+
+        Ok(Self {
+            id: entry.id.clone(),
+            title: entry
+                .title
+                .as_ref()
+                .map(|title| title.content.trim().to_string()),
+            link: entry.links.first().map(|link| link.href.clone()),
+            summary: summary.filter(|summary| !summary.is_empty()),
+        })
+    }
+}
+
+/// Feeds commonly embed HTML in their summaries, which is not readable in a notification.
+fn plain_text(content: &str, html: bool) -> Result<String, DominionRequestError> {
+    if html {
+        Ok(text::html_to_text(content)?.trim().to_string())
+    } else {
+        Ok(content.trim().to_string())
+    }
+}
+
+fn is_html(subtype: &str) -> bool {
+    subtype == "html" || subtype == "xhtml"
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::feed::*;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+<channel>
+    <title>News</title>
+    <link>https://example.com</link>
+    <description>Latest news</description>
+    <item>
+        <guid>https://example.com/2</guid>
+        <title>Second post</title>
+        <link>https://example.com/posts/2</link>
+        <description>&lt;p&gt;Something &lt;b&gt;new&lt;/b&gt;&lt;/p&gt;</description>
+    </item>
+    <item>
+        <guid>https://example.com/1</guid>
+        <title>First post</title>
+        <link>https://example.com/posts/1</link>
+    </item>
+</channel>
+</rss>"#;
+
+    #[test]
+    fn new_items() {
+        let items = parse_items(FEED.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "https://example.com/2");
+        assert_eq!(items[0].summary.as_deref(), Some("Something **new**"));
+
+        let old = json::pretty(&serde_json::to_value(&items[1..]).unwrap()).unwrap();
+        let new = json::pretty(&serde_json::to_value(&items).unwrap()).unwrap();
+
+        let source = FeedSource::new(&WatchEntry::default(), &HttpConfig::default()).unwrap();
+        let events = source.changes("https://example.com/feed", old.as_str(), new.as_str());
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            NotificationEvent::NewItem { title: Some(title), link: Some(link), .. }
+                if title == "Second post" && link == "https://example.com/posts/2"
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use async_trait::async_trait;
+use tracing::trace;
+
+use crate::config::WatchEntry;
+use crate::error::DominionRequestError;
+use crate::source::{is_binary, BinaryContent, Response, Source};
+
+/// Reads a file, or lists the contents of a directory, from the local filesystem.
+#[derive(Debug)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(entry: &WatchEntry) -> Self {
+        let path = entry.url.strip_prefix("file://").unwrap_or(&entry.url);
+        Self {
+            path: PathBuf::from(path),
+        }
+    }
+
+    /// Lists all files inside a directory, recursively, one per line along with their size and
+    /// modification time.
+    async fn list_dir(&self) -> Result<String, DominionRequestError> {
+        let mut lines = vec![];
+        let mut pending = vec![self.path.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await.map_err(io_err(&dir))?;
+            while let Some(entry) = entries.next_entry().await.map_err(io_err(&dir))? {
+                let path = entry.path();
+                let metadata = entry.metadata().await.map_err(io_err(&path))?;
+                let name = path.strip_prefix(&self.path).unwrap_or(&path).display();
+
+                if metadata.is_dir() {
+                    lines.push(format!("{name}/"));
+                    pending.push(path);
+                } else {
+                    let mtime = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                        .map(|mtime| mtime.as_secs())
+                        .unwrap_or_default();
+                    lines.push(format!("{name}\t{}\t{mtime}", metadata.len()));
+                }
+            }
+        }
+
+        lines.sort();
+        Ok(lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl Source for FileSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        trace!("Reading {}", self.path.display());
+        let metadata = tokio::fs::metadata(&self.path)
+            .await
+            .map_err(io_err(&self.path))?;
+
+        let (content, binary) = if metadata.is_dir() {
+            (self.list_dir().await?, None)
+        } else {
+            let bytes = tokio::fs::read(&self.path)
+                .await
+                .map_err(io_err(&self.path))?;
+            if is_binary(None, &bytes) {
+                (String::new(), Some(BinaryContent::new(bytes, None)))
+            } else {
+                (String::from_utf8_lossy(&bytes).into_owned(), None)
+            }
+        };
+
+        Ok(Response {
+            content,
+            status: None,
+            metadata: vec![],
+            warnings: vec![],
+            binary,
+            elapsed: None,
+        })
+    }
+}
+
+fn io_err(path: &Path) -> impl Fn(std::io::Error) -> DominionRequestError + '_ {
+    move |e| DominionRequestError::Io {
+        path: format!("{}", path.display()),
+        source: e,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::file::*;
+
+    #[tokio::test]
+    async fn list_dir() {
+        let dir = std::env::temp_dir().join("dominion-test-file-source");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("reports")).unwrap();
+        std::fs::write(dir.join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.join("reports").join("b.csv"), "1,2,3").unwrap();
+
+        let entry = WatchEntry {
+            url: format!("file://{}", dir.display()),
+            ..WatchEntry::default()
+        };
+        let content = FileSource::new(&entry).fetch().await.unwrap().content;
+        let lines = content
+            .lines()
+            .map(|line| line.rsplit_once('\t').map_or(line, |(rest, _mtime)| rest))
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec!["a.txt\t5", "reports/", "reports/b.csv\t5"]);
+    }
+
+    #[tokio::test]
+    async fn encoding() {
+        let dir = std::env::temp_dir().join("dominion-test-file-encoding");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Text that is not UTF-8 is still compared as text
+        let file = dir.join("latin1.txt");
+        std::fs::write(&file, b"caf\xe9").unwrap();
+        let entry = WatchEntry {
+            url: format!("{}", file.display()),
+            ..WatchEntry::default()
+        };
+        let response = FileSource::new(&entry).fetch().await.unwrap();
+        assert_eq!(response.content, "caf\u{fffd}");
+        assert!(response.binary.is_none());
+
+        let file = dir.join("image.png");
+        std::fs::write(&file, b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        let entry = WatchEntry {
+            url: format!("{}", file.display()),
+            ..WatchEntry::default()
+        };
+        let response = FileSource::new(&entry).fetch().await.unwrap();
+        assert!(response.binary.is_some());
+    }
+}
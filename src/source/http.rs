@@ -0,0 +1,598 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{redirect, Certificate, Client, Identity, Method, Proxy, RequestBuilder, StatusCode};
+use serde::de::IgnoredAny;
+use tokio::time::Instant;
+use tracing::{debug, info, trace};
+
+use crate::config::{parse_pair, HttpConfig, RedirectPolicy, WatchEntry};
+use crate::error::{DominionConfigError, DominionError, DominionRequestError};
+use crate::expect::StatusRange;
+use crate::source::auth::Auth;
+use crate::source::session::Session;
+use crate::source::{is_binary, is_text_type, BinaryContent, Response, Source, Status};
+use crate::transform::json;
+
+static DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Fetches content over HTTP(S).
+#[derive(Debug)]
+pub struct HttpSource {
+    url: String,
+    method: Method,
+    headers: Vec<(String, String)>,
+    body: Option<RequestBody>,
+    auth: Option<Auth>,
+    session: Option<Session>,
+    validators: Validators,
+    /// Validators of the last response, only used once its content has been committed.
+    pending_validators: Option<Validators>,
+    /// Statuses to accept instead of any 2xx, if not empty.
+    accepted_statuses: Vec<StatusRange>,
+    http_client: Client,
+}
+
+/// Identify the version of the content last fetched, to only fetch it again if modified.
+#[derive(Debug, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+#[derive(Debug)]
+enum RequestBody {
+    Text(String),
+    File(String),
+    Json(serde_json::Value),
+}
+
+impl HttpSource {
+    pub fn new(entry: &WatchEntry, http_cfg: &HttpConfig) -> Result<Self, DominionError> {
+        let headers = entry
+            .headers
+            .iter()
+            .map(|h| parse_pair(h, "header", "name=value"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let body = match (&entry.body, &entry.body_file, &entry.json) {
+            (None, None, None) => None,
+            (Some(body), None, None) => Some(RequestBody::Text(body.clone())),
+            (None, Some(path), None) => Some(RequestBody::File(path.clone())),
+            (None, None, Some(value)) => Some(RequestBody::Json(value.clone())),
+            _ => {
+                return Err(DominionConfigError::ConflictingBodies {
+                    url: entry.url.clone(),
+                }
+                .into())
+            }
+        };
+
+        let session = match &entry.session {
+            Some(session) => Some(Session::new(session)?),
+            None => None,
+        };
+
+        let mut accepted_statuses = entry
+            .expect
+            .iter()
+            .flat_map(|expect| expect.status.iter())
+            .map(|status| StatusRange::parse(status))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut http_cfg = http_cfg.merge(&entry.http);
+
+        // Watching the status or redirects is pointless if they are failures or followed
+        let watches_status = entry.include.iter().any(|name| {
+            name.eq_ignore_ascii_case("status") || name.eq_ignore_ascii_case("location")
+        });
+        if watches_status {
+            if accepted_statuses.is_empty() {
+                accepted_statuses.push(StatusRange::any());
+            }
+            http_cfg.redirect = http_cfg.redirect.or(Some(RedirectPolicy::None));
+        }
+
+        if http_cfg.client_cert.is_some() != http_cfg.client_key.is_some() {
+            return Err(DominionConfigError::IncompleteClientIdentity {
+                url: entry.url.clone(),
+            }
+            .into());
+        }
+        let http_client = build_client(&http_cfg, session.is_some())?;
+
+        Ok(Self {
+            url: entry.url.clone(),
+            method: entry.method.clone(),
+            headers,
+            body,
+            auth: entry.auth.as_ref().map(Auth::new),
+            session,
+            validators: Validators::default(),
+            pending_validators: None,
+            accepted_statuses,
+            http_client,
+        })
+    }
+
+    /// Requests any URL with the same method, body, headers and client settings as the watched one.
+    pub async fn request(&self, url: &str) -> Result<Response, DominionRequestError> {
+        let validators = Validators::default();
+        self.request_if_modified(url, &self.method, self.body.as_ref(), &validators)
+            .await
+    }
+
+    /// Requests any URL with a plain GET, with the same headers and client settings as the
+    /// watched one.
+    pub async fn get(&self, url: &str) -> Result<Response, DominionRequestError> {
+        let validators = Validators::default();
+        self.request_if_modified(url, &Method::GET, None, &validators)
+            .await
+    }
+
+    /// Requests a URL, unless it has not been modified since the response the validators were
+    /// taken from. In that case, an empty response with status 304 is returned.
+    async fn request_if_modified(
+        &self,
+        url: &str,
+        method: &Method,
+        body: Option<&RequestBody>,
+        validators: &Validators,
+    ) -> Result<Response, DominionRequestError> {
+        let (res, start) = match &self.session {
+            Some(session) => {
+                session.ensure(&self.http_client).await?;
+                let (res, start) = self.send(url, method, body, validators).await?;
+                if session.is_expired(&res) {
+                    info!("Session of {url} expired, logging in again");
+                    session.login(&self.http_client).await?;
+                    self.send(url, method, body, validators).await?
+                } else {
+                    (res, start)
+                }
+            }
+            None => self.send(url, method, body, validators).await?,
+        };
+        let status = res.status();
+
+        let metadata = res
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(Response {
+                content: String::new(),
+                status: Some(Status::Http(status)),
+                metadata,
+                warnings: vec![],
+                binary: None,
+                elapsed: Some(start.elapsed()),
+            });
+        }
+
+        // Only decode the body as text when it is one, as decoding would mangle binary content
+        let content_type = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let (content, binary) = match content_type.as_deref() {
+            Some(content_type) if is_text_type(content_type) => (res.text().await?, None),
+            _ => {
+                let bytes = res.bytes().await?;
+                if is_binary(content_type.as_deref(), &bytes) {
+                    (
+                        String::new(),
+                        Some(BinaryContent::new(bytes.to_vec(), content_type)),
+                    )
+                } else {
+                    (String::from_utf8_lossy(&bytes).to_string(), None)
+                }
+            }
+        };
+
+        let response = Response {
+            content,
+            status: Some(Status::Http(status)),
+            metadata,
+            warnings: vec![],
+            binary,
+            elapsed: Some(start.elapsed()),
+        };
+
+        let accepted = if self.accepted_statuses.is_empty() {
+            status.is_success()
+        } else {
+            self.accepted_statuses
+                .iter()
+                .any(|range| range.contains(status))
+        };
+        if !accepted {
+            // The token may have been revoked, so get a new one on the next request
+            if status == StatusCode::UNAUTHORIZED
+                && let Some(auth) = &self.auth
+            {
+                auth.invalidate().await;
+            }
+
+            // Make JSON error bodies readable, if possible
+            let body = if json::is_json(&response) {
+                serde_json::from_str(response.content.as_str())
+                    .map_err(DominionRequestError::from)
+                    .and_then(|value| json::pretty(&value))
+                    .unwrap_or(response.content)
+            } else {
+                response.content
+            };
+
+            return Err(DominionRequestError::HttpRequestFailed {
+                url: url.to_string(),
+                status,
+                body,
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Sends a request, returning the response along with when it was sent, to time it.
+    async fn send(
+        &self,
+        url: &str,
+        method: &Method,
+        body: Option<&RequestBody>,
+        validators: &Validators,
+    ) -> Result<(reqwest::Response, Instant), DominionRequestError> {
+        let mut req = self.build_request(url, method, body).await?;
+        if let Some(etag) = &validators.etag {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        trace!("Fetching {url}: {req:?}");
+        let start = Instant::now();
+        let res = req.send().await?;
+        trace!("Fetched {url}: {res:?}");
+        Ok((res, start))
+    }
+
+    async fn build_request(
+        &self,
+        url: &str,
+        method: &Method,
+        body: Option<&RequestBody>,
+    ) -> Result<RequestBuilder, DominionRequestError> {
+        let mut req = self.http_client.request(method.clone(), url);
+
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+
+        if let Some(auth) = &self.auth {
+            req = auth.apply(req, &self.http_client).await?;
+        }
+
+        if let Some(body) = body {
+            let (content, is_json) = match body {
+                RequestBody::Text(text) => (text.clone().into_bytes(), false),
+                RequestBody::File(path) => {
+                    let content =
+                        tokio::fs::read(path)
+                            .await
+                            .map_err(|e| DominionRequestError::Io {
+                                path: path.clone(),
+                                source: e,
+                            })?;
+                    (content, false)
+                }
+                RequestBody::Json(value) => (serde_json::to_vec(value)?, true),
+            };
+
+            // Explicitly configured headers take precedence
+            let has_content_type = self
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+            if !has_content_type {
+                let content_type = if is_json
+                    || serde_json::from_slice::<IgnoredAny>(content.as_slice()).is_ok()
+                {
+                    "application/json"
+                } else {
+                    "text/plain; charset=utf-8"
+                };
+                req = req.header(CONTENT_TYPE, content_type);
+            }
+
+            req = req.body(content);
+        }
+
+        Ok(req)
+    }
+}
+
+fn build_client(http_cfg: &HttpConfig, cookies: bool) -> Result<Client, DominionRequestError> {
+    let mut user_agent = http_cfg.user_agent.clone().unwrap_or_default();
+    if user_agent.is_empty() {
+        user_agent = DEFAULT_USER_AGENT.to_string();
+    }
+    debug!("Using user agent: {user_agent}");
+
+    let redirect = match http_cfg.redirect.unwrap_or_default() {
+        RedirectPolicy::Follow => redirect::Policy::default(),
+        RedirectPolicy::None => redirect::Policy::none(),
+        RedirectPolicy::Limit(max) => redirect::Policy::limited(max),
+    };
+
+    let mut builder = Client::builder()
+        .user_agent(user_agent)
+        .cookie_store(cookies)
+        .connect_timeout(http_cfg.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT))
+        .read_timeout(http_cfg.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT))
+        .timeout(http_cfg.timeout.unwrap_or(DEFAULT_TIMEOUT))
+        .redirect(redirect)
+        .danger_accept_invalid_certs(http_cfg.accept_invalid_certs.unwrap_or(false));
+
+    if let Some(proxy) = &http_cfg.proxy {
+        builder = builder.proxy(Proxy::all(proxy.as_str())?);
+    }
+
+    if let Some(ca_bundle) = &http_cfg.ca_bundle {
+        for certificate in Certificate::from_pem_bundle(read_file(ca_bundle)?.as_slice())? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if let (Some(cert), Some(key)) = (&http_cfg.client_cert, &http_cfg.client_key) {
+        let identity =
+            Identity::from_pkcs8_pem(read_file(cert)?.as_slice(), read_file(key)?.as_slice())?;
+        builder = builder.identity(identity);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, DominionRequestError> {
+    std::fs::read(path).map_err(|e| DominionRequestError::Io {
+        path: path.to_string(),
+        source: e,
+    })
+}
+
+#[async_trait]
+impl Source for HttpSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let response = self
+            .request_if_modified(
+                self.url.as_str(),
+                &self.method,
+                self.body.as_ref(),
+                &self.validators,
+            )
+            .await?;
+
+        // Other methods are not meant to be cached, and servers may not even send the validators
+        if self.method == Method::GET && !response.is_not_modified() {
+            self.pending_validators = Some(Validators {
+                etag: response.metadata(ETAG.as_str()).map(str::to_string),
+                last_modified: response
+                    .metadata(LAST_MODIFIED.as_str())
+                    .map(str::to_string),
+            });
+        }
+
+        Ok(response)
+    }
+
+    fn commit(&mut self) {
+        if let Some(validators) = self.pending_validators.take() {
+            self.validators = validators;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.validators = Validators::default();
+        self.pending_validators = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::http::*;
+    use crate::source::mock;
+    use crate::transform::Transformer;
+
+    #[tokio::test]
+    async fn body() {
+        let entry = WatchEntry {
+            url: "https://example.com/graphql".to_string(),
+            method: Method::POST,
+            json: Some(json!({ "query": "{ items { id } }" })),
+            ..WatchEntry::default()
+        };
+        let source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let req = source
+            .build_request(entry.url.as_str(), &source.method, source.body.as_ref())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(
+            req.body().and_then(|body| body.as_bytes()),
+            Some(br#"{"query":"{ items { id } }"}"#.as_slice())
+        );
+
+        let entry = WatchEntry {
+            body: Some("q=price".to_string()),
+            headers: vec!["Content-Type=application/x-www-form-urlencoded".to_string()],
+            ..entry
+        };
+        assert!(matches!(
+            HttpSource::new(&entry, &HttpConfig::default()),
+            Err(DominionError::Config(
+                DominionConfigError::ConflictingBodies { .. }
+            ))
+        ));
+
+        let entry = WatchEntry {
+            json: None,
+            ..entry
+        };
+        let source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let req = source
+            .build_request(entry.url.as_str(), &source.method, source.body.as_ref())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let content_types = req
+            .headers()
+            .get_all(CONTENT_TYPE)
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(content_types, ["application/x-www-form-urlencoded"]);
+    }
+
+    #[tokio::test]
+    async fn read_timeout() {
+        // Accepts requests, but never answers
+        let server = mock::serve(|_| None).await;
+
+        let entry = WatchEntry {
+            url: server,
+            http: HttpConfig {
+                read_timeout: Some(Duration::from_millis(200)),
+                ..HttpConfig::default()
+            },
+            ..WatchEntry::default()
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), source.fetch()).await;
+        assert!(matches!(result, Ok(Err(DominionRequestError::Http(_)))));
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        // Accepts requests, but never answers
+        let server = mock::serve(|_| None).await;
+
+        let entry = WatchEntry {
+            url: server,
+            http: HttpConfig {
+                timeout: Some(Duration::from_millis(200)),
+                ..HttpConfig::default()
+            },
+            ..WatchEntry::default()
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), source.fetch()).await;
+        assert!(matches!(result, Ok(Err(DominionRequestError::Http(e))) if e.is_timeout()));
+    }
+
+    #[test]
+    fn incomplete_client_identity() {
+        let entry = WatchEntry {
+            http: HttpConfig {
+                client_cert: Some("client.pem".to_string()),
+                ..HttpConfig::default()
+            },
+            ..WatchEntry::default()
+        };
+        assert!(matches!(
+            HttpSource::new(&entry, &HttpConfig::default()),
+            Err(DominionError::Config(
+                DominionConfigError::IncompleteClientIdentity { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn not_modified() {
+        // Serves the same content, unless the client already has it
+        let server = mock::serve(|request| {
+            let response = if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                mock::response("304 Not Modified", &[("ETag", "\"v1\"")], "")
+            } else {
+                mock::response("200 OK", &[("ETag", "\"v1\"")], "Hello")
+            };
+            Some(response)
+        })
+        .await;
+
+        let entry = WatchEntry {
+            url: server,
+            ..WatchEntry::default()
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+
+        let response = source.fetch().await.unwrap();
+        assert!(!response.is_not_modified());
+        assert_eq!(response.content, "Hello");
+
+        // Until the content is committed, it is fetched in full
+        let response = source.fetch().await.unwrap();
+        assert!(!response.is_not_modified());
+
+        source.commit();
+        let response = source.fetch().await.unwrap();
+        assert!(response.is_not_modified());
+        assert_eq!(response.content, "");
+
+        source.reset();
+        let response = source.fetch().await.unwrap();
+        assert_eq!(response.content, "Hello");
+    }
+
+    #[tokio::test]
+    async fn include_redirect() {
+        let server = mock::serve(|request| {
+            let response = if request.starts_with("GET /old ") {
+                mock::response("301 Moved Permanently", &[("Location", "/new")], "")
+            } else {
+                mock::response("200 OK", &[], "Hello")
+            };
+            Some(response)
+        })
+        .await;
+
+        let entry = WatchEntry {
+            url: format!("{server}/old"),
+            include: vec!["status".to_string(), "location".to_string()],
+            ..WatchEntry::default()
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let response = source.fetch().await.unwrap();
+        assert_eq!(
+            Transformer::new(&entry).unwrap().apply(&response).unwrap(),
+            "Status: 301 Moved Permanently\nlocation: /new\n\n"
+        );
+
+        // Following the redirect explicitly still works
+        let entry = WatchEntry {
+            http: HttpConfig {
+                redirect: Some(RedirectPolicy::Follow),
+                ..HttpConfig::default()
+            },
+            ..entry
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+        let response = source.fetch().await.unwrap();
+        assert_eq!(response.content, "Hello");
+    }
+}
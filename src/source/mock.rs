@@ -0,0 +1,71 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Starts a minimal HTTP server for tests, that answers each request with the response built by
+/// the handler from the raw request. Connections the handler returns no response for are kept
+/// open, but never answered. Returns the base URL of the server.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(&str) -> Option<String> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let mut unanswered = vec![];
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let Some(request) = read_request(&mut stream).await else {
+                continue;
+            };
+
+            match handler(request.as_str()) {
+                Some(response) => stream.write_all(response.as_bytes()).await.unwrap(),
+                None => unanswered.push(stream),
+            }
+        }
+    });
+
+    format!("http://{address}")
+}
+
+/// Builds a response, closing the connection after it.
+pub fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut response = format!("HTTP/1.1 {status}\r\n");
+    for (name, value) in headers {
+        response += format!("{name}: {value}\r\n").as_str();
+    }
+    response += format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .as_str();
+    response
+}
+
+/// Reads until the whole request has been received, or the client hangs up.
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut request = String::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = stream.read(&mut buf).await.ok()?;
+        if len == 0 {
+            return None;
+        }
+        request += String::from_utf8_lossy(&buf[..len]).as_ref();
+
+        if let Some((head, body)) = request.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length: ")
+                        .map(|l| l.parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= length {
+                return Some(request);
+            }
+        }
+    }
+}
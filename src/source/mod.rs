@@ -0,0 +1,346 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+
+use crate::config::{HttpConfig, WatchEntry};
+use crate::error::{DominionConfigError, DominionError, DominionRequestError};
+use crate::source::command::CommandSource;
+use crate::source::dns::DnsSource;
+use crate::source::feed::FeedSource;
+use crate::source::file::FileSource;
+use crate::source::http::HttpSource;
+use crate::source::sitemap::SitemapSource;
+use crate::source::tls::TlsSource;
+use crate::NotificationEvent;
+
+mod auth;
+mod command;
+mod dns;
+mod feed;
+mod file;
+mod http;
+#[cfg(test)]
+pub mod mock;
+mod session;
+mod sitemap;
+mod tls;
+
+/// A backend able to fetch the content to be watched, selected by the `protocol` of a watch entry.
+#[async_trait]
+pub trait Source: Debug + Send + Sync {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError>;
+
+    /// Called once the content of the last fetch has been stored, so that later fetches may only
+    /// return it again if it changed.
+    fn commit(&mut self) {}
+
+    /// Forgets the content committed last, so that the next fetch returns it in full.
+    fn reset(&mut self) {}
+
+    /// State to keep across restarts, e.g. the items of a feed already seen.
+    fn state(&self) -> Option<String> {
+        None
+    }
+
+    /// Restores the state kept before the last restart.
+    fn restore(&mut self, _state: &str) -> Result<(), DominionRequestError> {
+        Ok(())
+    }
+
+    /// Describes how the content changed, as the events to be notified.
+    fn changes(&self, url: &str, old: &str, new: &str) -> Vec<NotificationEvent> {
+        vec![NotificationEvent::Changed {
+            url: url.to_string(),
+            old: old.to_string(),
+            new: new.to_string(),
+        }]
+    }
+}
+
+/// Content fetched by a [Source].
+#[derive(Debug)]
+pub struct Response {
+    pub content: String,
+    pub status: Option<Status>,
+    /// Extra information about the content, e.g. the HTTP response headers.
+    pub metadata: Vec<(String, String)>,
+    /// Issues that deserve attention despite the fetch having succeeded.
+    pub warnings: Vec<String>,
+    /// The raw content, if it is not text. The content is then left empty.
+    pub binary: Option<BinaryContent>,
+    /// Time taken by the request for the content itself, excluding preparations like logging in.
+    /// If not set, the whole fetch is timed instead.
+    pub elapsed: Option<Duration>,
+}
+
+/// Content that cannot be represented as text, like images or documents.
+#[derive(Debug, Clone)]
+pub struct BinaryContent {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+    /// SHA-256 of the bytes, in hexadecimal.
+    pub hash: String,
+}
+
+impl BinaryContent {
+    pub fn new(bytes: Vec<u8>, content_type: Option<String>) -> Self {
+        let hash = format!("{:x}", Sha256::digest(bytes.as_slice()));
+        Self {
+            bytes,
+            content_type,
+            hash,
+        }
+    }
+
+    /// Summarizes the content, so that it can be compared and stored like text.
+    pub fn describe(&self) -> String {
+        format!(
+            "Content type: {}\nSize: {} bytes\nSHA-256: {}\n",
+            self.content_type.as_deref().unwrap_or("unknown"),
+            self.bytes.len(),
+            self.hash
+        )
+    }
+
+    /// File extension matching the content type, to name attachments.
+    pub fn extension(&self) -> &'static str {
+        match essence(self.content_type.as_deref().unwrap_or_default()).as_str() {
+            "application/pdf" => "pdf",
+            "application/zip" => "zip",
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/svg+xml" => "svg",
+            _ => "bin",
+        }
+    }
+}
+
+/// Whether some content should be handled as binary, based on its declared type if it is known to
+/// be text or binary, or otherwise on it containing NUL bytes. Text in another encoding than UTF-8
+/// is still text, if lossy.
+pub fn is_binary(content_type: Option<&str>, bytes: &[u8]) -> bool {
+    match content_type.map(essence) {
+        Some(essence) if is_text_essence(essence.as_str()) => false,
+        Some(essence) if is_binary_essence(essence.as_str()) => true,
+        _ => bytes.contains(&0),
+    }
+}
+
+/// Whether a declared type is known to be text, which can then be decoded as such.
+pub fn is_text_type(content_type: &str) -> bool {
+    is_text_essence(essence(content_type).as_str())
+}
+
+/// The type without its parameters, e.g. `text/html` for `text/html; charset=utf-8`.
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn is_text_essence(essence: &str) -> bool {
+    let (kind, subtype) = essence.split_once('/').unwrap_or((essence, ""));
+
+    kind == "text"
+        || matches!(
+            subtype,
+            "json" | "xml" | "javascript" | "ecmascript" | "x-www-form-urlencoded"
+        )
+        || subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+}
+
+fn is_binary_essence(essence: &str) -> bool {
+    let (kind, subtype) = essence.split_once('/').unwrap_or((essence, ""));
+
+    matches!(kind, "image" | "audio" | "video" | "font")
+        || matches!(
+            subtype,
+            "pdf"
+                | "zip"
+                | "gzip"
+                | "x-gzip"
+                | "x-tar"
+                | "x-bzip2"
+                | "x-xz"
+                | "x-7z-compressed"
+                | "vnd.rar"
+                | "x-rar-compressed"
+                | "wasm"
+                | "msword"
+                | "vnd.ms-excel"
+                | "vnd.ms-powerpoint"
+        )
+        || subtype.starts_with("vnd.openxmlformats-officedocument.")
+        || subtype.starts_with("vnd.oasis.opendocument.")
+}
+
+impl Response {
+    /// Whether the source reported the content to be the same as in the previous fetch, without
+    /// sending it again.
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self.status, Some(Status::Http(StatusCode::NOT_MODIFIED)))
+    }
+
+    /// Finds the value of a metadata entry, ignoring the case of its name.
+    pub fn metadata(&self, name: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Outcome of a fetch, as reported by the source.
+#[derive(Debug, Clone, Copy)]
+pub enum Status {
+    Http(StatusCode),
+    Exit(ExitStatus),
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Http(status) => write!(f, "{status}"),
+            Status::Exit(status) => write!(f, "{status}"),
+        }
+    }
+}
+
+/// Creates the source matching the protocol of a watch entry.
+pub fn from_entry(
+    entry: &WatchEntry,
+    http_cfg: &HttpConfig,
+) -> Result<Box<dyn Source>, DominionError> {
+    let unsupported = |option: &str| DominionConfigError::UnsupportedOption {
+        url: entry.url.clone(),
+        protocol: entry.protocol.clone(),
+        option: option.to_string(),
+    };
+
+    // Only HTTP responses have a status to check
+    let checks_status = entry
+        .expect
+        .as_ref()
+        .is_some_and(|expect| !expect.status.is_empty());
+    if checks_status && matches!(entry.protocol.as_str(), "file" | "command" | "tls" | "dns") {
+        return Err(unsupported("expect.status").into());
+    }
+
+    // The content of feeds and sitemaps is rebuilt, without the headers of the response
+    if !entry.include.is_empty() && matches!(entry.protocol.as_str(), "feed" | "sitemap") {
+        return Err(unsupported("include").into());
+    }
+
+    let source: Box<dyn Source> = match entry.protocol.as_str() {
+        "" | "http" => Box::new(HttpSource::new(entry, http_cfg)?),
+        "file" => Box::new(FileSource::new(entry)),
+        "command" => Box::new(CommandSource::new(entry)?),
+        "tls" => Box::new(TlsSource::new(entry)?),
+        "dns" => Box::new(DnsSource::new(entry)?),
+        "feed" => Box::new(FeedSource::new(entry, http_cfg)?),
+        "sitemap" => Box::new(SitemapSource::new(entry, http_cfg)?),
+        protocol => {
+            return Err(DominionConfigError::UnknownProtocol {
+                url: entry.url.clone(),
+                protocol: protocol.to_string(),
+            }
+            .into())
+        }
+    };
+    Ok(source)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::ExpectConfig;
+    use crate::source::*;
+
+    #[test]
+    fn binary() {
+        assert!(!is_binary(Some("text/html; charset=utf-8"), b"<html>"));
+        assert!(!is_binary(Some("application/ld+json"), b"{}"));
+        assert!(is_binary(Some("image/png"), b"\x89PNG"));
+        assert!(is_binary(None, b"\xff\xfe\x00"));
+        assert!(!is_binary(None, "plain text".as_bytes()));
+        assert!(!is_binary(None, b"caf\xe9"));
+        assert!(is_binary(Some("application/zip"), b"PK"));
+        assert!(is_binary(Some("font/woff2"), b"wOF2"));
+        assert!(!is_binary(Some("image/svg+xml"), b"<svg>"));
+
+        // Types that are not known to be binary are sniffed
+        assert!(!is_binary(Some("application/x-ndjson"), b"{}\n{}\n"));
+        assert!(!is_binary(Some("application/yaml"), b"key: value"));
+        assert!(!is_binary(Some("application/toml"), b"key = 'value'"));
+        assert!(!is_binary(Some("application/octet-stream"), b"plain text"));
+        assert!(is_binary(
+            Some("application/octet-stream"),
+            b"\x7fELF\x02\0"
+        ));
+
+        let content = BinaryContent::new(b"%PDF".to_vec(), Some("application/pdf".to_string()));
+        assert_eq!(content.extension(), "pdf");
+        assert_eq!(
+            content.describe(),
+            "Content type: application/pdf\nSize: 4 bytes\nSHA-256: \
+            315d429b7714cedb6ad04ac31240145257692630457f3c88253c5beceac76027\n"
+        );
+    }
+
+    #[test]
+    fn unknown_protocol() {
+        let entry = WatchEntry {
+            protocol: "gopher".to_string(),
+            ..WatchEntry::default()
+        };
+        let result = from_entry(&entry, &HttpConfig::default());
+        assert!(matches!(
+            result,
+            Err(DominionError::Config(
+                DominionConfigError::UnknownProtocol { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn unsupported_options() {
+        let entry = WatchEntry {
+            protocol: "command".to_string(),
+            url: "true".to_string(),
+            expect: Some(ExpectConfig {
+                status: vec!["2xx".to_string()],
+                ..ExpectConfig::default()
+            }),
+            ..WatchEntry::default()
+        };
+        let result = from_entry(&entry, &HttpConfig::default());
+        assert!(matches!(
+            result,
+            Err(DominionError::Config(
+                DominionConfigError::UnsupportedOption { .. }
+            ))
+        ));
+
+        let entry = WatchEntry {
+            protocol: "feed".to_string(),
+            include: vec!["etag".to_string()],
+            ..WatchEntry::default()
+        };
+        let result = from_entry(&entry, &HttpConfig::default());
+        assert!(matches!(
+            result,
+            Err(DominionError::Config(
+                DominionConfigError::UnsupportedOption { .. }
+            ))
+        ));
+    }
+}
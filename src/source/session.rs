@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use regex::Regex;
+use reqwest::{Client, Method, StatusCode, Url};
+use tracing::{debug, info, trace};
+
+use crate::config::{parse_pair, SessionConfig};
+use crate::error::{DominionError, DominionRequestError};
+
+/// Logs in by running a sequence of requests, whose cookies are kept by the HTTP client.
+#[derive(Debug)]
+pub struct Session {
+    steps: Vec<Step>,
+    login_url: Option<Url>,
+    logged_in: AtomicBool,
+}
+
+#[derive(Debug)]
+struct Step {
+    url: String,
+    method: Method,
+    headers: Vec<(String, String)>,
+    form: Vec<(String, String)>,
+    body: Option<String>,
+    extract: Vec<(String, Regex)>,
+}
+
+impl Session {
+    pub fn new(cfg: &SessionConfig) -> Result<Self, DominionError> {
+        let mut steps = Vec::with_capacity(cfg.steps.len());
+        for step in &cfg.steps {
+            let mut extract = vec![];
+            for value in &step.extract {
+                let (name, pattern) = parse_pair(value, "extract", "name=regex")?;
+                let regex = Regex::new(pattern.as_str()).map_err(DominionRequestError::from)?;
+                extract.push((name, regex));
+            }
+
+            steps.push(Step {
+                url: step.url.clone(),
+                method: step.method.clone(),
+                headers: step
+                    .headers
+                    .iter()
+                    .map(|h| parse_pair(h, "header", "name=value"))
+                    .collect::<Result<_, _>>()?,
+                form: step
+                    .form
+                    .iter()
+                    .map(|f| parse_pair(f, "form field", "name=value"))
+                    .collect::<Result<_, _>>()?,
+                body: step.body.clone(),
+                extract,
+            });
+        }
+
+        let login_url = cfg
+            .login_url
+            .as_ref()
+            .or(cfg.steps.first().map(|step| &step.url))
+            .and_then(|url| Url::parse(url).ok());
+
+        Ok(Self {
+            steps,
+            login_url,
+            logged_in: AtomicBool::new(false),
+        })
+    }
+
+    /// Logs in, unless already done.
+    pub async fn ensure(&self, client: &Client) -> Result<(), DominionRequestError> {
+        if self.logged_in.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.login(client).await
+    }
+
+    pub async fn login(&self, client: &Client) -> Result<(), DominionRequestError> {
+        self.logged_in.store(false, Ordering::Relaxed);
+
+        let mut values = HashMap::new();
+        for (idx, step) in self.steps.iter().enumerate() {
+            let url = substitute(step.url.as_str(), &values);
+            let failed = |reason: String| DominionRequestError::SessionFailed {
+                step: idx + 1,
+                url: url.clone(),
+                reason,
+            };
+
+            let mut req = client.request(step.method.clone(), url.as_str());
+            for (name, value) in &step.headers {
+                req = req.header(name, substitute(value, &values));
+            }
+            if !step.form.is_empty() {
+                let form = step
+                    .form
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), substitute(value, &values)))
+                    .collect::<Vec<_>>();
+                req = req.form(&form);
+            }
+            if let Some(body) = &step.body {
+                req = req.body(substitute(body, &values));
+            }
+
+            debug!("Running login step #{} on {url}", idx + 1);
+            let res = req.send().await?;
+            let status = res.status();
+            trace!("Ran login step #{} on {url}: {res:?}", idx + 1);
+            if !status.is_success() {
+                return Err(failed(format!("unexpected status {status}")));
+            }
+
+            let content = res.text().await?;
+            for (name, regex) in &step.extract {
+                let Some(captures) = regex.captures(content.as_str()) else {
+                    return Err(failed(format!("'{name}' not found in the response")));
+                };
+                let value = captures.get(1).or(captures.get(0)).unwrap().as_str();
+                values.insert(name.clone(), value.to_string());
+            }
+        }
+
+        info!("Logged in after {} steps", self.steps.len());
+        self.logged_in.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Whether a response shows that the session is no longer valid.
+    pub fn is_expired(&self, res: &reqwest::Response) -> bool {
+        if res.status() == StatusCode::UNAUTHORIZED || res.status() == StatusCode::FORBIDDEN {
+            return true;
+        }
+
+        // Redirected to the login page
+        self.login_url.as_ref().is_some_and(|login| {
+            let url = res.url();
+            url.host_str() == login.host_str() && url.path() == login.path()
+        })
+    }
+}
+
+/// Replaces the `${name}` placeholders with the values extracted so far.
+fn substitute(value: &str, values: &HashMap<String, String>) -> String {
+    let mut value = value.to_string();
+    for (name, replacement) in values {
+        value = value.replace(format!("${{{name}}}").as_str(), replacement);
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    use crate::config::{HttpConfig, SessionStep, WatchEntry};
+    use crate::source::http::HttpSource;
+    use crate::source::mock;
+    use crate::source::session::*;
+    use crate::source::Source;
+
+    /// A site whose pages require a session cookie, obtained by submitting the login form along
+    /// with its CSRF token. Bumping `generation` expires all sessions.
+    async fn mock_site(logins: Arc<AtomicUsize>, generation: Arc<AtomicUsize>) -> String {
+        mock::serve(move |request| {
+            let session = format!("sid={}", generation.load(Ordering::SeqCst));
+            let response = if request.starts_with("GET /login") {
+                mock::response("200 OK", &[], r#"<input name="csrf" value="abc123">"#)
+            } else if request.starts_with("POST /login") {
+                assert!(request.contains("csrf=abc123&user=admin"), "{request}");
+                logins.fetch_add(1, Ordering::SeqCst);
+                mock::response("200 OK", &[("Set-Cookie", session.as_str())], "Welcome")
+            } else if request
+                .to_lowercase()
+                .contains(format!("cookie: {session}").as_str())
+            {
+                mock::response("200 OK", &[], "Secret page")
+            } else {
+                mock::response("302 Found", &[("Location", "/login")], "")
+            };
+            Some(response)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn login() {
+        let logins = Arc::new(AtomicUsize::new(0));
+        let generation = Arc::new(AtomicUsize::new(0));
+        let site = mock_site(logins.clone(), generation.clone()).await;
+
+        let entry = WatchEntry {
+            url: format!("{site}/page"),
+            session: Some(SessionConfig {
+                steps: vec![
+                    SessionStep {
+                        url: format!("{site}/login"),
+                        extract: vec![r#"csrf=name="csrf" value="([^"]+)""#.to_string()],
+                        ..SessionStep::default()
+                    },
+                    SessionStep {
+                        url: format!("{site}/login"),
+                        method: Method::POST,
+                        form: vec!["csrf=${csrf}".to_string(), "user=admin".to_string()],
+                        ..SessionStep::default()
+                    },
+                ],
+                login_url: None,
+            }),
+            ..WatchEntry::default()
+        };
+        let mut source = HttpSource::new(&entry, &HttpConfig::default()).unwrap();
+
+        assert_eq!(source.fetch().await.unwrap().content, "Secret page");
+        assert_eq!(source.fetch().await.unwrap().content, "Secret page");
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+
+        // Expired sessions are redirected to the login page, and the login redone
+        generation.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(source.fetch().await.unwrap().content, "Secret page");
+        assert_eq!(logins.load(Ordering::SeqCst), 2);
+    }
+}
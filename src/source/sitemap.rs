@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use roxmltree::{Document, Node};
+use tracing::trace;
+
+use crate::config::{HttpConfig, WatchEntry};
+use crate::error::{DominionError, DominionRequestError};
+use crate::source::http::HttpSource;
+use crate::source::{Response, Source};
+use crate::NotificationEvent;
+
+/// Fetches a sitemap over HTTP(S), following any nested sitemap indexes, and lists its URLs.
+///
+/// Each line holds a URL and, if known, when it was last modified, separated by a tab.
+#[derive(Debug)]
+pub struct SitemapSource {
+    url: String,
+    http: HttpSource,
+}
+
+/// The entries of a single sitemap file.
+#[derive(Debug, PartialEq)]
+enum Sitemap {
+    /// Pages, with their `lastmod`.
+    Urls(Vec<(String, Option<String>)>),
+    /// Other sitemaps to follow.
+    Index(Vec<String>),
+}
+
+impl SitemapSource {
+    pub fn new(entry: &WatchEntry, http_cfg: &HttpConfig) -> Result<Self, DominionError> {
+        Ok(Self {
+            url: entry.url.clone(),
+            http: HttpSource::new(entry, http_cfg)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Source for SitemapSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let mut urls = BTreeMap::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.url.clone()];
+        let mut status = None;
+        let mut elapsed = Duration::ZERO;
+
+        while let Some(sitemap_url) = pending.pop() {
+            // Indexes may reference each other, so only follow each sitemap once
+            if !visited.insert(sitemap_url.clone()) {
+                continue;
+            }
+
+            // The request configured for the entry is meant for it alone, not the nested sitemaps
+            let response = if sitemap_url == self.url {
+                self.http.request(sitemap_url.as_str()).await?
+            } else {
+                self.http.get(sitemap_url.as_str()).await?
+            };
+            status = status.or(response.status);
+            elapsed += response.elapsed.unwrap_or_default();
+
+            match parse_sitemap(sitemap_url.as_str(), response.content.as_str())? {
+                Sitemap::Urls(entries) => {
+                    trace!("Found {} URLs in {sitemap_url}", entries.len());
+                    urls.extend(entries);
+                }
+                Sitemap::Index(sitemaps) => {
+                    trace!("Found {} nested sitemaps in {sitemap_url}", sitemaps.len());
+                    // Reversed, to follow them in the order they were listed
+                    pending.extend(sitemaps.into_iter().rev());
+                }
+            }
+        }
+
+        let mut content = String::new();
+        for (url, lastmod) in urls {
+            content += url.as_str();
+            if let Some(lastmod) = lastmod {
+                content += "\t";
+                content += lastmod.as_str();
+            }
+            content += "\n";
+        }
+
+        Ok(Response {
+            content,
+            status,
+            metadata: vec![],
+            warnings: vec![],
+            binary: None,
+            elapsed: Some(elapsed),
+        })
+    }
+
+    fn changes(&self, url: &str, old: &str, new: &str) -> Vec<NotificationEvent> {
+        let old = parse_lines(old);
+        let new = parse_lines(new);
+
+        let added = new
+            .keys()
+            .filter(|page| !old.contains_key(*page))
+            .map(|page| page.to_string())
+            .collect::<Vec<_>>();
+        let removed = old
+            .keys()
+            .filter(|page| !new.contains_key(*page))
+            .map(|page| page.to_string())
+            .collect::<Vec<_>>();
+        let modified = new
+            .iter()
+            .filter(|(page, lastmod)| old.get(*page).is_some_and(|old| old != *lastmod))
+            .map(|(page, _)| page.to_string())
+            .collect::<Vec<_>>();
+
+        if added.is_empty() && removed.is_empty() && modified.is_empty() {
+            return vec![];
+        }
+
+        vec![NotificationEvent::SitemapChanged {
+            url: url.to_string(),
+            added,
+            removed,
+            modified,
+        }]
+    }
+}
+
+fn parse_sitemap(url: &str, content: &str) -> Result<Sitemap, DominionRequestError> {
+    let document = Document::parse(content)?;
+    let root = document.root_element();
+
+    // Namespaces are ignored, as not all sitemaps declare the standard one
+    match root.tag_name().name() {
+        "urlset" => Ok(Sitemap::Urls(
+            children(root, "url")
+                .filter_map(|node| {
+                    let loc = child_text(node, "loc")?;
+                    Some((loc, child_text(node, "lastmod")))
+                })
+                .collect(),
+        )),
+        "sitemapindex" => Ok(Sitemap::Index(
+            children(root, "sitemap")
+                .filter_map(|node| child_text(node, "loc"))
+                .collect(),
+        )),
+        name => Err(DominionRequestError::InvalidSitemap {
+            url: url.to_string(),
+            reason: format!("unexpected root element '{name}'"),
+        }),
+    }
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn child_text(node: Node, name: &'static str) -> Option<String> {
+    children(node, name)
+        .next()
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Reads back the URLs listed by [SitemapSource::fetch], mapped to their `lastmod`.
+fn parse_lines(content: &str) -> BTreeMap<&str, &str> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split_once('\t').unwrap_or((line, "")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, OnceLock};
+
+    use reqwest::Method;
+
+    use crate::source::mock;
+    use crate::source::sitemap::*;
+
+    #[test]
+    fn parse() {
+        let index = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    <sitemap><loc>https://example.com/pages.xml</loc></sitemap>
+    <sitemap><loc> https://example.com/posts.xml </loc><lastmod>2024-01-01</lastmod></sitemap>
+</sitemapindex>"#;
+        assert_eq!(
+            parse_sitemap("index.xml", index).unwrap(),
+            Sitemap::Index(vec![
+                "https://example.com/pages.xml".to_string(),
+                "https://example.com/posts.xml".to_string(),
+            ])
+        );
+
+        let urlset = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    <url><loc>https://example.com/</loc><lastmod>2024-01-01</lastmod></url>
+    <url><loc>https://example.com/about</loc></url>
+</urlset>"#;
+        assert_eq!(
+            parse_sitemap("pages.xml", urlset).unwrap(),
+            Sitemap::Urls(vec![
+                (
+                    "https://example.com/".to_string(),
+                    Some("2024-01-01".to_string())
+                ),
+                ("https://example.com/about".to_string(), None),
+            ])
+        );
+
+        assert!(parse_sitemap("feed.xml", "<rss></rss>").is_err());
+    }
+
+    #[tokio::test]
+    async fn nested() {
+        // Only the index is meant to be requested with the configured method and body
+        let base = Arc::new(OnceLock::<String>::new());
+        let server = mock::serve({
+            let base = base.clone();
+            move |request| {
+                let body = if request.starts_with("POST /index.xml") {
+                    assert!(request.ends_with("\r\n\r\nsite=main"), "{request}");
+                    format!(
+                        "<sitemapindex><sitemap><loc>{}/pages.xml</loc></sitemap></sitemapindex>",
+                        base.get().unwrap()
+                    )
+                } else if request.starts_with("GET /pages.xml") {
+                    assert!(request.ends_with("\r\n\r\n"), "{request}");
+                    "<urlset><url><loc>https://example.com/</loc></url></urlset>".to_string()
+                } else {
+                    panic!("unexpected request: {request}");
+                };
+                Some(mock::response("200 OK", &[], body.as_str()))
+            }
+        })
+        .await;
+        base.set(server.clone()).unwrap();
+
+        let entry = WatchEntry {
+            url: format!("{server}/index.xml"),
+            method: Method::POST,
+            body: Some("site=main".to_string()),
+            ..WatchEntry::default()
+        };
+        let mut source = SitemapSource::new(&entry, &HttpConfig::default()).unwrap();
+        assert_eq!(
+            source.fetch().await.unwrap().content,
+            "https://example.com/\n"
+        );
+    }
+
+    #[test]
+    fn changes() {
+        let old = "https://example.com/\t2024-01-01\n\
+            https://example.com/about\n\
+            https://example.com/old\t2023-01-01\n";
+        let new = "https://example.com/\t2024-02-01\n\
+            https://example.com/about\n\
+            https://example.com/new\n";
+
+        let entry = WatchEntry::default();
+        let source = SitemapSource::new(&entry, &HttpConfig::default()).unwrap();
+        let events = source.changes("https://example.com/sitemap.xml", old, new);
+        assert!(matches!(
+            events.as_slice(),
+            [NotificationEvent::SitemapChanged { added, removed, modified, .. }]
+                if added == &["https://example.com/new"]
+                    && removed == &["https://example.com/old"]
+                    && modified == &["https://example.com/"]
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tracing::trace;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::config::{format_duration, WatchEntry};
+use crate::error::DominionRequestError;
+use crate::source::{Response, Source};
+
+const DEFAULT_TLS_PORT: u16 = 443;
+
+/// Connects to a TLS server and describes its certificate chain.
+#[derive(Debug)]
+pub struct TlsSource {
+    host: String,
+    port: u16,
+    timeout: Duration,
+    expiry_threshold: Duration,
+    tls_config: Arc<ClientConfig>,
+}
+
+impl TlsSource {
+    pub fn new(entry: &WatchEntry) -> Result<Self, DominionRequestError> {
+        let address = entry.url.strip_prefix("tls://").unwrap_or(&entry.url);
+        let (host, port) =
+            parse_address(address).ok_or_else(|| DominionRequestError::InvalidAddress {
+                address: entry.url.clone(),
+            })?;
+
+        // The certificates are inspected, not trusted, so that invalid ones can still be reported
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth();
+
+        Ok(Self {
+            host,
+            port,
+            timeout: entry.timeout,
+            expiry_threshold: entry.expiry_threshold,
+            tls_config: Arc::new(config),
+        })
+    }
+
+    /// The address to connect to, with IPv6 hosts in brackets.
+    fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    async fn peer_certificates(
+        &self,
+    ) -> Result<Vec<CertificateDer<'static>>, DominionRequestError> {
+        let address = self.address();
+        let server_name = ServerName::try_from(self.host.clone())?;
+
+        let connect = async {
+            let stream = TcpStream::connect(address.as_str()).await?;
+            let connector = TlsConnector::from(self.tls_config.clone());
+            connector.connect(server_name, stream).await
+        };
+        let stream = tokio::time::timeout(self.timeout, connect)
+            .await
+            .map_err(|_elapsed| DominionRequestError::Timeout {
+                address: address.clone(),
+                timeout: format_duration(&self.timeout),
+            })?
+            .map_err(|e| DominionRequestError::Connect {
+                address: address.clone(),
+                source: e,
+            })?;
+
+        let (_, connection) = stream.get_ref();
+        let certificates = connection
+            .peer_certificates()
+            .map(|certs| certs.to_vec())
+            .unwrap_or_default();
+        Ok(certificates)
+    }
+}
+
+#[async_trait]
+impl Source for TlsSource {
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        trace!("Connecting to {}", self.address());
+        let certificates = self.peer_certificates().await?;
+        trace!(
+            "Received {} certificates from {}",
+            certificates.len(),
+            self.address()
+        );
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time went backwards")
+            .as_secs() as i64;
+        let threshold = now.saturating_add(self.expiry_threshold.as_secs() as i64);
+
+        let mut content = String::new();
+        let mut warnings = vec![];
+        for (idx, der) in certificates.iter().enumerate() {
+            let (_, cert) = X509Certificate::from_der(der.as_ref())
+                .map_err(|e| DominionRequestError::Certificate(e.to_string()))?;
+            content += describe_certificate(idx, der.as_ref(), &cert).as_str();
+
+            let not_after = cert.validity().not_after;
+            if not_after.timestamp() <= now {
+                warnings.push(format!(
+                    "Certificate '{}' expired on {not_after}",
+                    cert.subject()
+                ));
+            } else if not_after.timestamp() <= threshold {
+                warnings.push(format!(
+                    "Certificate '{}' expires on {not_after}, in less than {}",
+                    cert.subject(),
+                    format_duration(&self.expiry_threshold)
+                ));
+            }
+        }
+
+        Ok(Response {
+            content,
+            status: None,
+            metadata: vec![],
+            warnings,
+            binary: None,
+            elapsed: None,
+        })
+    }
+}
+
+/// Splits an address into host and port, defaulting to the TLS port. IPv6 hosts are accepted both
+/// bare, like `::1`, and in brackets, like `[::1]:8443`.
+fn parse_address(address: &str) -> Option<(String, u16)> {
+    let parse_port = |port: &str| port.parse::<u16>().ok();
+
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => parse_port(port)?,
+            None if rest.is_empty() => DEFAULT_TLS_PORT,
+            None => return None,
+        };
+        return Some((host.to_string(), port));
+    }
+
+    if address.parse::<Ipv6Addr>().is_ok() {
+        return Some((address.to_string(), DEFAULT_TLS_PORT));
+    }
+
+    match address.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), parse_port(port)?)),
+        None => Some((address.to_string(), DEFAULT_TLS_PORT)),
+    }
+}
+
+/// Describes the details of a certificate that, when changed, should be reported.
+fn describe_certificate(idx: usize, der: &[u8], cert: &X509Certificate) -> String {
+    let fingerprint = Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let sans = match cert.subject_alternative_name() {
+        Ok(Some(san)) => san
+            .value
+            .general_names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => String::new(),
+    };
+
+    format!(
+        "Certificate #{idx}\n\
+        Subject: {}\n\
+        Issuer: {}\n\
+        Serial: {}\n\
+        SANs: {sans}\n\
+        Not before: {}\n\
+        Not after: {}\n\
+        SHA-256 fingerprint: {fingerprint}\n\n",
+        cert.subject(),
+        cert.issuer(),
+        cert.raw_serial_as_string(),
+        cert.validity().not_before,
+        cert.validity().not_after,
+    )
+}
+
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rcgen::{date_time_ymd, CertificateParams, KeyPair};
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use rustls::ServerConfig;
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    use crate::source::tls::*;
+
+    #[test]
+    fn address() {
+        assert_eq!(
+            parse_address("example.com"),
+            Some(("example.com".to_string(), 443))
+        );
+        assert_eq!(
+            parse_address("example.com:8443"),
+            Some(("example.com".to_string(), 8443))
+        );
+        assert_eq!(parse_address("::1"), Some(("::1".to_string(), 443)));
+        assert_eq!(parse_address("[::1]"), Some(("::1".to_string(), 443)));
+        assert_eq!(
+            parse_address("[2001:db8::1]:8443"),
+            Some(("2001:db8::1".to_string(), 8443))
+        );
+        assert_eq!(parse_address("[::1]8443"), None);
+        assert_eq!(parse_address("example.com:https"), None);
+    }
+
+    #[tokio::test]
+    async fn self_signed() {
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        params.not_after = date_time_ymd(2030, 1, 1);
+        let key = KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let server_config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = acceptor.accept(stream).await;
+        });
+
+        let entry = WatchEntry {
+            protocol: "tls".to_string(),
+            url: format!("localhost:{port}"),
+            timeout: Duration::from_secs(5),
+            // Far enough in the future to always include the certificate expiry
+            expiry_threshold: Duration::from_secs(60 * 60 * 24 * 365 * 100),
+            ..WatchEntry::default()
+        };
+        let response = TlsSource::new(&entry).unwrap().fetch().await.unwrap();
+
+        let fingerprint = Sha256::digest(cert.der().as_ref())
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+        assert!(response.content.contains("SANs: DNSName(localhost)"));
+        assert!(response.content.contains(fingerprint.as_str()));
+        assert_eq!(response.warnings.len(), 1);
+    }
+}
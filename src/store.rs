@@ -0,0 +1,534 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, trace};
+
+use crate::config::{HistoryConfig, HttpConfig, WatchEntry};
+use crate::error::DominionStoreError;
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+const HISTORY_DIR: &str = "history";
+
+/// A change detected by a watcher, as kept in the history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Epoch time of the change, in milliseconds.
+    pub timestamp: u64,
+    pub url: String,
+    /// SHA-256 of the content before the change, in hexadecimal.
+    pub old_hash: String,
+    /// SHA-256 of the content after the change, in hexadecimal.
+    pub new_hash: String,
+    pub body: String,
+}
+
+impl HistoryRecord {
+    pub fn new(url: &str, old: &str, new: &str) -> Self {
+        Self {
+            timestamp: now_millis(),
+            url: url.to_string(),
+            old_hash: format!("{:x}", Sha256::digest(old.as_bytes())),
+            new_hash: format!("{:x}", Sha256::digest(new.as_bytes())),
+            body: new.to_string(),
+        }
+    }
+}
+
+/// Persists the state of each watcher on disk, so that it survives restarts.
+#[derive(Debug, Clone)]
+pub struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    pub fn new(dir: PathBuf) -> Result<Self, DominionStoreError> {
+        for sub_dir in [SNAPSHOTS_DIR, HISTORY_DIR] {
+            let sub_dir = dir.join(sub_dir);
+            std::fs::create_dir_all(&sub_dir).map_err(|e| DominionStoreError::CreateDir {
+                dir: format!("{}", sub_dir.display()),
+                source: e,
+            })?;
+        }
+
+        Ok(Self { dir })
+    }
+
+    /// Builds a key that uniquely identifies a watch entry, from its explicit ID or otherwise from
+    /// every setting that affects the fetched or transformed content. Secrets are taken as
+    /// configured, e.g. `${env:TOKEN}`, so that rotating them keeps the baseline.
+    pub fn key(entry: &WatchEntry) -> String {
+        let entry = entry.unresolved.as_deref().unwrap_or(entry);
+
+        let mut hasher = Sha256::new();
+        if let Some(id) = &entry.id {
+            hasher.update(b"id\0");
+            hasher.update(id.as_bytes());
+            return format!("{:x}", hasher.finalize());
+        }
+
+        // Scheduling, credentials, client settings and the checks made on the content do not
+        // change the content itself, so tuning them keeps the baseline
+        let content_settings = WatchEntry {
+            auth: None,
+            session: None,
+            http: HttpConfig::default(),
+            timeout: Duration::ZERO,
+            expiry_threshold: Duration::ZERO,
+            interval: Duration::ZERO,
+            variation: 0.0,
+            stagger: Duration::ZERO,
+            ignore: vec![],
+            history: HistoryConfig::default(),
+            expect: None,
+            ..entry.clone()
+        };
+        let json = serde_json::to_vec(&content_settings).expect("watch entry is serializable");
+        hasher.update(json.as_slice());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Loads the last known content of a watch entry, if any.
+    pub async fn load_snapshot(&self, key: &str) -> Result<Option<String>, DominionStoreError> {
+        let file = self.snapshot_file(key);
+        trace!("Loading snapshot from '{}'", file.display());
+
+        match tokio::fs::read_to_string(&file).await {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(DominionStoreError::Io {
+                file: format!("{}", file.display()),
+                source: err,
+            }),
+        }
+    }
+
+    /// Replaces the last known content of a watch entry.
+    pub async fn save_snapshot(&self, key: &str, content: &str) -> Result<(), DominionStoreError> {
+        let file = self.snapshot_file(key);
+        trace!("Saving snapshot to '{}'", file.display());
+        write_atomically(&file, content.as_bytes()).await
+    }
+
+    /// Loads the last known raw content of a watch entry, kept when it is not text.
+    pub async fn load_blob(&self, key: &str) -> Result<Option<Vec<u8>>, DominionStoreError> {
+        let file = self.blob_file(key);
+        trace!("Loading blob from '{}'", file.display());
+
+        match tokio::fs::read(&file).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(DominionStoreError::Io {
+                file: format!("{}", file.display()),
+                source: err,
+            }),
+        }
+    }
+
+    /// Replaces the last known raw content of a watch entry.
+    pub async fn save_blob(&self, key: &str, bytes: &[u8]) -> Result<(), DominionStoreError> {
+        let file = self.blob_file(key);
+        trace!("Saving blob to '{}'", file.display());
+        write_atomically(&file, bytes).await
+    }
+
+    /// Loads the state the source of a watch entry keeps across restarts, if any.
+    pub async fn load_state(&self, key: &str) -> Result<Option<String>, DominionStoreError> {
+        let file = self.state_file(key);
+        trace!("Loading state from '{}'", file.display());
+
+        match tokio::fs::read_to_string(&file).await {
+            Ok(state) => Ok(Some(state)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(DominionStoreError::Io {
+                file: format!("{}", file.display()),
+                source: err,
+            }),
+        }
+    }
+
+    /// Replaces the state the source of a watch entry keeps across restarts.
+    pub async fn save_state(&self, key: &str, state: &str) -> Result<(), DominionStoreError> {
+        let file = self.state_file(key);
+        trace!("Saving state to '{}'", file.display());
+        write_atomically(&file, state.as_bytes()).await
+    }
+
+    /// Discards the last known raw content of a watch entry, once its content is text again.
+    pub async fn delete_blob(&self, key: &str) -> Result<(), DominionStoreError> {
+        let file = self.blob_file(key);
+        match tokio::fs::remove_file(&file).await {
+            Ok(()) => {
+                trace!("Deleted blob '{}'", file.display());
+                Ok(())
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(DominionStoreError::Io {
+                file: format!("{}", file.display()),
+                source: err,
+            }),
+        }
+    }
+
+    /// Adds a change to the history of a watch entry, and discards the changes that fall outside
+    /// the retention policy.
+    pub async fn record_change(
+        &self,
+        key: &str,
+        record: &HistoryRecord,
+        retention: &HistoryConfig,
+    ) -> Result<(), DominionStoreError> {
+        if retention.max_entries == 0 {
+            return Ok(());
+        }
+
+        let dir = self.dir.join(HISTORY_DIR).join(key);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| DominionStoreError::CreateDir {
+                dir: format!("{}", dir.display()),
+                source: e,
+            })?;
+
+        let json = serde_json::to_vec_pretty(record)?;
+
+        // Changes recorded within the same millisecond are told apart by a sequence number
+        let mut sequence = 0;
+        let (file, mut handle) = loop {
+            let file = dir.join(record_file_name(record.timestamp, sequence));
+            let result = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&file)
+                .await;
+            match result {
+                Ok(handle) => break (file, handle),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => sequence += 1,
+                Err(err) => {
+                    return Err(DominionStoreError::Io {
+                        file: format!("{}", file.display()),
+                        source: err,
+                    })
+                }
+            }
+        };
+        trace!("Recording change to '{}'", file.display());
+
+        handle
+            .write_all(json.as_slice())
+            .await
+            .map_err(|e| DominionStoreError::Io {
+                file: format!("{}", file.display()),
+                source: e,
+            })?;
+
+        self.prune_records(&dir, record.timestamp, retention).await
+    }
+
+    /// Discards the changes of a watch entry that fall outside the retention policy, e.g. on
+    /// startup, as they are otherwise only discarded when a new change is recorded.
+    pub async fn prune_history(
+        &self,
+        key: &str,
+        retention: &HistoryConfig,
+    ) -> Result<(), DominionStoreError> {
+        let dir = self.dir.join(HISTORY_DIR).join(key);
+        if retention.max_entries == 0 || !tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+            return Ok(());
+        }
+
+        self.prune_records(&dir, now_millis(), retention).await
+    }
+
+    async fn prune_records(
+        &self,
+        dir: &Path,
+        now: u64,
+        retention: &HistoryConfig,
+    ) -> Result<(), DominionStoreError> {
+        let io_err = |e: std::io::Error| DominionStoreError::Io {
+            file: format!("{}", dir.display()),
+            source: e,
+        };
+
+        let mut records = vec![];
+        let mut entries = tokio::fs::read_dir(dir).await.map_err(io_err)?;
+        while let Some(entry) = entries.next_entry().await.map_err(io_err)? {
+            let path = entry.path();
+            let record = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(parse_record_file_stem);
+            if let Some(record) = record {
+                records.push(record);
+            }
+        }
+
+        for (timestamp, sequence) in expired_records(records, now, retention) {
+            let file = dir.join(record_file_name(timestamp, sequence));
+            debug!("Discarding old change '{}'", file.display());
+            tokio::fs::remove_file(&file).await.map_err(io_err)?;
+        }
+
+        Ok(())
+    }
+
+    fn snapshot_file(&self, key: &str) -> PathBuf {
+        self.dir.join(SNAPSHOTS_DIR).join(format!("{key}.snapshot"))
+    }
+
+    fn blob_file(&self, key: &str) -> PathBuf {
+        self.dir.join(SNAPSHOTS_DIR).join(format!("{key}.bin"))
+    }
+
+    fn state_file(&self, key: &str) -> PathBuf {
+        self.dir.join(SNAPSHOTS_DIR).join(format!("{key}.state"))
+    }
+}
+
+/// Writes to a temporary file first, so that a crash never leaves a half-written file. The
+/// temporary file is named after the whole file name, e.g. `<key>.bin.tmp`, so that the files of
+/// a watch entry never share it.
+async fn write_atomically(file: &Path, content: &[u8]) -> Result<(), DominionStoreError> {
+    let mut tmp_name = file.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_file = file.with_file_name(tmp_name);
+    let io_err = |e: std::io::Error| DominionStoreError::Io {
+        file: format!("{}", file.display()),
+        source: e,
+    };
+    tokio::fs::write(&tmp_file, content).await.map_err(io_err)?;
+    tokio::fs::rename(&tmp_file, file).await.map_err(io_err)?;
+
+    Ok(())
+}
+
+/// Names the file of a record as `<timestamp>.json`, or `<timestamp>-<sequence>.json` if other
+/// records were made within the same millisecond.
+fn record_file_name(timestamp: u64, sequence: u64) -> String {
+    if sequence == 0 {
+        format!("{timestamp}.json")
+    } else {
+        format!("{timestamp}-{sequence}.json")
+    }
+}
+
+fn parse_record_file_stem(stem: &str) -> Option<(u64, u64)> {
+    match stem.split_once('-') {
+        Some((timestamp, sequence)) => Some((timestamp.parse().ok()?, sequence.parse().ok()?)),
+        None => Some((stem.parse().ok()?, 0)),
+    }
+}
+
+/// Lists the records, by timestamp and sequence number, that are no longer covered by the
+/// retention policy.
+/// Epoch time, in milliseconds.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_millis() as u64
+}
+
+fn expired_records(
+    mut records: Vec<(u64, u64)>,
+    now: u64,
+    retention: &HistoryConfig,
+) -> Vec<(u64, u64)> {
+    // Newest first
+    records.sort_unstable_by(|a, b| b.cmp(a));
+
+    let min_timestamp = retention
+        .max_age
+        .map(|max_age| now.saturating_sub(max_age.as_millis() as u64))
+        .unwrap_or(0);
+
+    records
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, (timestamp, _))| *idx >= retention.max_entries || *timestamp < min_timestamp)
+        .map(|(_, record)| record)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::Config;
+    use crate::store::*;
+
+    #[test]
+    fn key() {
+        let entry = WatchEntry {
+            protocol: "dns".to_string(),
+            url: "example.com".to_string(),
+            records: vec!["A".to_string()],
+            ..WatchEntry::default()
+        };
+        let key = Store::key(&entry);
+
+        let other_records = WatchEntry {
+            records: vec!["MX".to_string()],
+            ..entry.clone()
+        };
+        assert_ne!(Store::key(&other_records), key);
+
+        let other_render = WatchEntry {
+            render: crate::config::Render::Text,
+            ..entry.clone()
+        };
+        assert_ne!(Store::key(&other_render), key);
+
+        let other_interval = WatchEntry {
+            interval: Duration::from_secs(60),
+            ..entry.clone()
+        };
+        assert_eq!(Store::key(&other_interval), key);
+
+        let with_id = |url: &str| WatchEntry {
+            id: Some("prices".to_string()),
+            url: url.to_string(),
+            ..entry.clone()
+        };
+        assert_eq!(
+            Store::key(&with_id("example.com")),
+            Store::key(&with_id("example.org"))
+        );
+
+        let unresolved = WatchEntry {
+            url: "https://example.com/?token=${env:CARGO_PKG_NAME}".to_string(),
+            headers: vec!["Authorization=Bearer ${env:CARGO_PKG_NAME}".to_string()],
+            ..WatchEntry::default()
+        };
+        let mut config = Config {
+            watch: vec![unresolved.clone()],
+            ..Config::default()
+        };
+        config.resolve_secrets().unwrap();
+        let resolved = &config.watch[0];
+        assert_eq!(resolved.url, "https://example.com/?token=dominion");
+        assert_eq!(Store::key(resolved), Store::key(&unresolved));
+    }
+
+    #[test]
+    fn retention() {
+        let records = vec![(1_000, 0), (5_000, 0), (2_000, 0), (4_000, 0), (3_000, 0)];
+
+        let by_count = HistoryConfig {
+            max_entries: 3,
+            max_age: None,
+        };
+        assert_eq!(
+            expired_records(records.clone(), 5_000, &by_count),
+            vec![(2_000, 0), (1_000, 0)]
+        );
+
+        let by_age = HistoryConfig {
+            max_entries: 100,
+            max_age: Some(Duration::from_millis(1_500)),
+        };
+        assert_eq!(
+            expired_records(records, 5_000, &by_age),
+            vec![(3_000, 0), (2_000, 0), (1_000, 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn blob() {
+        let dir = std::env::temp_dir().join("dominion-test-store-blob");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::new(dir.clone()).unwrap();
+
+        assert_eq!(store.load_blob("key").await.unwrap(), None);
+        store.save_snapshot("key", "Size: 3 bytes").await.unwrap();
+        store.save_blob("key", b"\x89\0\xff").await.unwrap();
+        assert_eq!(
+            store.load_snapshot("key").await.unwrap().as_deref(),
+            Some("Size: 3 bytes")
+        );
+        assert_eq!(
+            store.load_blob("key").await.unwrap(),
+            Some(b"\x89\0\xff".to_vec())
+        );
+
+        let mut files = std::fs::read_dir(dir.join(SNAPSHOTS_DIR))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, vec!["key.bin", "key.snapshot"]);
+
+        store.delete_blob("key").await.unwrap();
+        store.delete_blob("key").await.unwrap();
+        assert_eq!(store.load_blob("key").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let dir = std::env::temp_dir().join("dominion-test-store-prune");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::new(dir.clone()).unwrap();
+
+        let record = HistoryRecord::new("https://example.com", "old", "new");
+        assert_eq!(
+            record.new_hash,
+            "11507a0e2f5e69d5dfa40a62a1bd7b6ee57e6bcd85c67c9b8431b36fff21c437"
+        );
+
+        // Recorded long ago, and never followed by another change
+        let record = HistoryRecord {
+            timestamp: 1_000,
+            ..record
+        };
+        let keep_all = HistoryConfig {
+            max_entries: 10,
+            max_age: None,
+        };
+        store
+            .record_change("key", &record, &keep_all)
+            .await
+            .unwrap();
+
+        let by_age = HistoryConfig {
+            max_entries: 10,
+            max_age: Some(Duration::from_secs(60 * 60 * 24)),
+        };
+        store.prune_history("key", &by_age).await.unwrap();
+        store.prune_history("unknown", &by_age).await.unwrap();
+        let files = std::fs::read_dir(dir.join(HISTORY_DIR).join("key")).unwrap();
+        assert_eq!(files.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn same_millisecond() {
+        let dir = std::env::temp_dir().join("dominion-test-store-same-millisecond");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::new(dir.clone()).unwrap();
+        let retention = HistoryConfig {
+            max_entries: 2,
+            max_age: None,
+        };
+
+        for body in ["first", "second", "third"] {
+            let record = HistoryRecord {
+                timestamp: 1_000,
+                url: "https://example.com".to_string(),
+                old_hash: String::new(),
+                new_hash: String::new(),
+                body: body.to_string(),
+            };
+            store
+                .record_change("key", &record, &retention)
+                .await
+                .unwrap();
+        }
+
+        let mut files = std::fs::read_dir(dir.join(HISTORY_DIR).join("key"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, vec!["1000-1.json", "1000-2.json"]);
+    }
+}
@@ -0,0 +1,72 @@
+use scraper::{Html, Selector};
+
+use crate::config::SelectAs;
+use crate::error::DominionConfigError;
+
+/// Keeps only the elements of an HTML document matched by a list of CSS selectors.
+#[derive(Debug)]
+pub struct HtmlSelector {
+    selectors: Vec<Selector>,
+    select_as: SelectAs,
+}
+
+impl HtmlSelector {
+    pub fn new(selectors: &[String], select_as: SelectAs) -> Result<Self, DominionConfigError> {
+        let selectors = selectors
+            .iter()
+            .map(|selector| {
+                Selector::parse(selector).map_err(|e| DominionConfigError::InvalidSelector {
+                    selector: selector.clone(),
+                    reason: e.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            selectors,
+            select_as,
+        })
+    }
+
+    pub fn select(&self, content: &str) -> String {
+        let document = Html::parse_document(content);
+
+        let mut matches = vec![];
+        for selector in &self.selectors {
+            for element in document.select(selector) {
+                let value = match self.select_as {
+                    SelectAs::Html => element.html(),
+                    SelectAs::Text => element.text().collect::<String>().trim().to_string(),
+                };
+                matches.push(value);
+            }
+        }
+
+        matches.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transform::html::*;
+
+    #[test]
+    fn select() {
+        let content = r#"<html><body>
+            <script>var nonce = "abc";</script>
+            <div class="ad">Buy now!</div>
+            <h1>Prices</h1>
+            <ul><li class="price">10 EUR</li><li class="price"><b>12</b> EUR</li></ul>
+        </body></html>"#;
+        let selectors = vec!["h1".to_string(), "li.price".to_string()];
+
+        let as_html = HtmlSelector::new(&selectors, SelectAs::Html).unwrap();
+        assert_eq!(
+            as_html.select(content),
+            "<h1>Prices</h1>\n<li class=\"price\">10 EUR</li>\n<li class=\"price\"><b>12</b> EUR</li>"
+        );
+
+        let as_text = HtmlSelector::new(&selectors, SelectAs::Text).unwrap();
+        assert_eq!(as_text.select(content), "Prices\n10 EUR\n12 EUR");
+    }
+}
@@ -0,0 +1,335 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Serializer, Value};
+use serde_json_path::JsonPath;
+
+use crate::config::{IgnoreMode, NormalizeConfig};
+use crate::error::{DominionConfigError, DominionRequestError};
+use crate::source::Response;
+
+/// Selects parts of a JSON document with a JSONPath expression.
+#[derive(Debug)]
+pub struct JsonQuery {
+    path: JsonPath,
+}
+
+impl JsonQuery {
+    pub fn new(expression: &str) -> Result<Self, DominionConfigError> {
+        let path =
+            JsonPath::parse(expression).map_err(|e| DominionConfigError::InvalidJsonQuery {
+                query: expression.to_string(),
+                reason: e.to_string(),
+            })?;
+        Ok(Self { path })
+    }
+
+    /// Returns all the nodes matched by the expression, as an array.
+    pub fn query(&self, value: &Value) -> Value {
+        let nodes = self.path.query(value).all();
+        Value::Array(nodes.into_iter().cloned().collect())
+    }
+}
+
+/// Removes, or blanks, the keys matched by a list of paths, e.g. `data.items.*.updatedAt`.
+///
+/// A `*` matches any key or array index, and a `**` matches any number of nested levels.
+#[derive(Debug)]
+pub struct IgnorePaths {
+    paths: Vec<Vec<Segment>>,
+    mode: IgnoreMode,
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Any,
+    AnyDepth,
+}
+
+impl Segment {
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            Segment::Key(expected) => expected == key,
+            Segment::Any | Segment::AnyDepth => true,
+        }
+    }
+}
+
+impl IgnorePaths {
+    pub fn new(paths: &[String], mode: IgnoreMode) -> Result<Self, DominionConfigError> {
+        let paths = paths
+            .iter()
+            .map(|path| {
+                let segments = path
+                    .strip_prefix("$.")
+                    .unwrap_or(path)
+                    .split('.')
+                    .map(|segment| match segment {
+                        "*" => Segment::Any,
+                        "**" => Segment::AnyDepth,
+                        key => Segment::Key(key.to_string()),
+                    })
+                    .collect::<Vec<_>>();
+
+                // There would be nothing left to match, so nothing would be ignored
+                if segments.last() == Some(&Segment::AnyDepth) {
+                    return Err(DominionConfigError::InvalidIgnorePath {
+                        path: path.clone(),
+                        reason: "'**' must be followed by a key or '*'".to_string(),
+                    });
+                }
+                Ok(segments)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { paths, mode })
+    }
+
+    pub fn apply(&self, value: &mut Value) {
+        for path in &self.paths {
+            self.apply_path(value, path.as_slice());
+        }
+    }
+
+    fn apply_path(&self, value: &mut Value, path: &[Segment]) {
+        let Some((segment, rest)) = path.split_first() else {
+            return;
+        };
+
+        if *segment == Segment::AnyDepth {
+            // Try matching the rest of the path on this level, and then on every level below
+            self.apply_path(value, rest);
+            match value {
+                Value::Object(map) => map.values_mut().for_each(|v| self.apply_path(v, path)),
+                Value::Array(vec) => vec.iter_mut().for_each(|v| self.apply_path(v, path)),
+                _ => {}
+            }
+            return;
+        }
+
+        if rest.is_empty() {
+            self.ignore(value, segment);
+            return;
+        }
+
+        match value {
+            Value::Object(map) => map
+                .iter_mut()
+                .filter(|(key, _)| segment.matches(key))
+                .for_each(|(_, v)| self.apply_path(v, rest)),
+            Value::Array(vec) => vec
+                .iter_mut()
+                .enumerate()
+                .filter(|(idx, _)| segment.matches(idx.to_string().as_str()))
+                .for_each(|(_, v)| self.apply_path(v, rest)),
+            _ => {}
+        }
+    }
+
+    fn ignore(&self, value: &mut Value, segment: &Segment) {
+        match (value, self.mode) {
+            (Value::Object(map), IgnoreMode::Remove) => map.retain(|key, _| !segment.matches(key)),
+            (Value::Object(map), IgnoreMode::Blank) => map
+                .iter_mut()
+                .filter(|(key, _)| segment.matches(key))
+                .for_each(|(_, v)| *v = Value::from("__ignored__")),
+            (Value::Array(vec), IgnoreMode::Remove) => {
+                let mut idx = 0;
+                vec.retain(|_| {
+                    let keep = !segment.matches(idx.to_string().as_str());
+                    idx += 1;
+                    keep
+                });
+            }
+            (Value::Array(vec), IgnoreMode::Blank) => vec
+                .iter_mut()
+                .enumerate()
+                .filter(|(idx, _)| segment.matches(idx.to_string().as_str()))
+                .for_each(|(_, v)| *v = Value::from("__ignored__")),
+            _ => {}
+        }
+    }
+}
+
+/// Brings a JSON document to a canonical form, so that equivalent documents are hashed the same.
+pub fn normalize(value: &mut Value, cfg: &NormalizeConfig) {
+    match value {
+        Value::Object(map) => {
+            if cfg.drop_nulls {
+                map.retain(|_, v| !v.is_null());
+            }
+            map.values_mut().for_each(|v| normalize(v, cfg));
+            if cfg.sort_keys {
+                map.sort_keys();
+            }
+        }
+        Value::Array(vec) => {
+            vec.iter_mut().for_each(|v| normalize(v, cfg));
+
+            let all_scalars = vec.iter().all(|v| !v.is_object() && !v.is_array());
+            let all_objects = vec.iter().all(|v| v.is_object());
+            if cfg.sort_arrays && all_scalars {
+                vec.sort_by(compare);
+            } else if let (Some(key), true) = (&cfg.sort_arrays_by, all_objects) {
+                vec.sort_by(|a, b| match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => compare(a, b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+pub fn is_json(response: &Response) -> bool {
+    response
+        .metadata("content-type")
+        .map(|ct| ct.contains("json"))
+        .unwrap_or(false)
+}
+
+pub fn pretty(value: &Value) -> Result<String, DominionRequestError> {
+    let mut buf = Vec::new();
+    let formatter = PrettyFormatter::with_indent(b"    ");
+    let mut ser = Serializer::with_formatter(&mut buf, formatter);
+    value.serialize(&mut ser)?;
+
+    String::from_utf8(buf).map_err(|_err| DominionRequestError::InvalidJson(value.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::transform::json::*;
+
+    #[test]
+    fn query() {
+        let value = json!({
+            "requestId": "abc",
+            "items": [
+                { "name": "foo", "price": 10 },
+                { "name": "bar", "price": 12 }
+            ]
+        });
+
+        let query = JsonQuery::new("$.items[*].price").unwrap();
+        assert_eq!(query.query(&value), json!([10, 12]));
+    }
+
+    #[test]
+    fn normalize_keys() {
+        let value = json!({ "b": 1, "a": { "d": 2, "c": 3 } });
+
+        let mut sorted = value.clone();
+        normalize(&mut sorted, &NormalizeConfig::default());
+        assert_eq!(sorted.to_string(), r#"{"a":{"c":3,"d":2},"b":1}"#);
+
+        let mut kept = value;
+        let cfg = NormalizeConfig {
+            sort_keys: false,
+            ..NormalizeConfig::default()
+        };
+        normalize(&mut kept, &cfg);
+        assert_eq!(kept.to_string(), r#"{"b":1,"a":{"d":2,"c":3}}"#);
+    }
+
+    #[test]
+    fn normalize_arrays() {
+        let cfg = NormalizeConfig {
+            sort_keys: true,
+            sort_arrays: true,
+            sort_arrays_by: Some("id".to_string()),
+            drop_nulls: true,
+        };
+
+        let mut a = json!({
+            "tags": ["b", "a", "c"],
+            "items": [{ "id": 2, "name": "bar" }, { "id": 1, "name": "foo", "note": null }],
+            "sizes": [10, 2, 1.5]
+        });
+        let mut b = json!({
+            "sizes": [1.5, 10, 2],
+            "items": [{ "name": "foo", "id": 1 }, { "name": "bar", "id": 2 }],
+            "tags": ["c", "b", "a"]
+        });
+        normalize(&mut a, &cfg);
+        normalize(&mut b, &cfg);
+
+        assert_eq!(pretty(&a).unwrap(), pretty(&b).unwrap());
+        assert_eq!(
+            a,
+            json!({
+                "items": [{ "id": 1, "name": "foo" }, { "id": 2, "name": "bar" }],
+                "sizes": [1.5, 2, 10],
+                "tags": ["a", "b", "c"]
+            })
+        );
+    }
+
+    #[test]
+    fn ignore_paths() {
+        let value = json!({
+            "requestId": "abc",
+            "data": {
+                "updatedAt": "2023-06-25T23:59:59.999Z",
+                "items": [
+                    { "name": "foo", "meta": { "updatedAt": "2023-06-25T23:59:59.999Z" } },
+                    { "name": "bar", "meta": { "updatedAt": "2023-06-25T23:59:59.999Z" } }
+                ]
+            }
+        });
+        let paths = vec![
+            "requestId".to_string(),
+            "data.items.*.meta".to_string(),
+            "**.updatedAt".to_string(),
+        ];
+
+        let mut removed = value.clone();
+        IgnorePaths::new(&paths, IgnoreMode::Remove)
+            .unwrap()
+            .apply(&mut removed);
+        assert_eq!(
+            removed,
+            json!({ "data": { "items": [{ "name": "foo" }, { "name": "bar" }] } })
+        );
+
+        let mut blanked = value;
+        IgnorePaths::new(&paths, IgnoreMode::Blank)
+            .unwrap()
+            .apply(&mut blanked);
+        assert_eq!(
+            blanked,
+            json!({
+                "requestId": "__ignored__",
+                "data": {
+                    "updatedAt": "__ignored__",
+                    "items": [
+                        { "name": "foo", "meta": "__ignored__" },
+                        { "name": "bar", "meta": "__ignored__" }
+                    ]
+                }
+            })
+        );
+
+        assert!(matches!(
+            IgnorePaths::new(&["data.**".to_string()], IgnoreMode::Remove),
+            Err(DominionConfigError::InvalidIgnorePath { .. })
+        ));
+    }
+}
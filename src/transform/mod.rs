@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::config::{NormalizeConfig, Render, WatchEntry};
+use crate::error::{DominionConfigError, DominionRequestError};
+use crate::source::Response;
+use crate::transform::html::HtmlSelector;
+use crate::transform::json::{IgnorePaths, JsonQuery};
+
+mod html;
+pub mod json;
+mod pdf;
+pub mod text;
+
+/// Narrows down the fetched content to the parts worth watching, before it is masked and hashed.
+#[derive(Debug)]
+pub struct Transformer {
+    json_query: Option<JsonQuery>,
+    ignore_paths: Option<IgnorePaths>,
+    normalize: Option<NormalizeConfig>,
+    html_selector: Option<HtmlSelector>,
+    render: Render,
+    include: Vec<String>,
+}
+
+impl Transformer {
+    pub fn new(entry: &WatchEntry) -> Result<Self, DominionConfigError> {
+        let json_query = match &entry.json_query {
+            Some(query) => Some(JsonQuery::new(query)?),
+            None => None,
+        };
+
+        let ignore_paths = if entry.ignore_paths.is_empty() {
+            None
+        } else {
+            Some(IgnorePaths::new(
+                &entry.ignore_paths,
+                entry.ignore_paths_mode,
+            )?)
+        };
+
+        let normalize = if entry.normalize == NormalizeConfig::default() {
+            None
+        } else {
+            Some(entry.normalize.clone())
+        };
+
+        let html_selector = if entry.selectors.is_empty() {
+            None
+        } else {
+            Some(HtmlSelector::new(&entry.selectors, entry.select_as)?)
+        };
+
+        Ok(Self {
+            json_query,
+            ignore_paths,
+            normalize,
+            html_selector,
+            render: entry.render,
+            include: entry.include.clone(),
+        })
+    }
+
+    pub fn apply(&self, response: &Response) -> Result<String, DominionRequestError> {
+        let content = self.apply_content(response)?;
+        if self.include.is_empty() {
+            return Ok(content);
+        }
+
+        // Placed before the content, so that changes to them are seen first
+        let mut included = String::new();
+        for name in &self.include {
+            if name.eq_ignore_ascii_case("status") {
+                if let Some(status) = &response.status {
+                    included += format!("Status: {status}\n").as_str();
+                }
+                continue;
+            }
+
+            for (_, value) in response
+                .metadata
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            {
+                included += format!("{name}: {value}\n").as_str();
+            }
+        }
+
+        Ok(format!("{included}\n{content}"))
+    }
+
+    /// Whether binary content is rendered as text, to be compared and notified like any other.
+    pub fn renders_binary(&self) -> bool {
+        self.render == Render::PdfText
+    }
+
+    /// The body of a response as text, with binary content and PDFs rendered as by
+    /// [Transformer::apply].
+    pub fn body<'a>(&self, response: &'a Response) -> Result<Cow<'a, str>, DominionRequestError> {
+        match (&response.binary, self.render) {
+            // Also for PDFs served as text, although decoding them may have broken them
+            (binary, Render::PdfText) => {
+                let bytes = match binary {
+                    Some(binary) => binary.bytes.as_slice(),
+                    None => response.content.as_bytes(),
+                };
+                pdf::pdf_to_text(bytes).map(Cow::Owned)
+            }
+            (Some(binary), _) => Ok(Cow::Owned(binary.describe())),
+            (None, _) => Ok(Cow::Borrowed(response.content.as_str())),
+        }
+    }
+
+    fn apply_content(&self, response: &Response) -> Result<String, DominionRequestError> {
+        if response.binary.is_some() || self.render == Render::PdfText {
+            return self.body(response).map(Cow::into_owned);
+        }
+
+        let has_json_options =
+            self.json_query.is_some() || self.ignore_paths.is_some() || self.normalize.is_some();
+        if has_json_options || json::is_json(response) {
+            return self.apply_json(response.content.as_str());
+        }
+
+        let mut content = response.content.clone();
+
+        if let Some(html_selector) = &self.html_selector {
+            content = html_selector.select(content.as_str());
+        }
+
+        if self.render == Render::Text {
+            content = text::html_to_text(content.as_str())?;
+        }
+
+        Ok(content)
+    }
+
+    fn apply_json(&self, content: &str) -> Result<String, DominionRequestError> {
+        let mut value = serde_json::from_str::<Value>(content)?;
+
+        if let Some(ignore_paths) = &self.ignore_paths {
+            ignore_paths.apply(&mut value);
+        }
+
+        if let Some(json_query) = &self.json_query {
+            value = json_query.query(&value);
+        }
+
+        // Even if not configured, as keys are sorted by default
+        let normalize = self.normalize.clone().unwrap_or_default();
+        json::normalize(&mut value, &normalize);
+
+        json::pretty(&value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+
+    use crate::source::Status;
+    use crate::transform::*;
+
+    #[test]
+    fn include() {
+        let entry = WatchEntry {
+            include: vec![
+                "status".to_string(),
+                "Location".to_string(),
+                "Cache-Control".to_string(),
+            ],
+            ..WatchEntry::default()
+        };
+        let transformer = Transformer::new(&entry).unwrap();
+        let response = Response {
+            content: "Moved".to_string(),
+            status: Some(Status::Http(StatusCode::MOVED_PERMANENTLY)),
+            metadata: vec![
+                (
+                    "location".to_string(),
+                    "https://example.com/new".to_string(),
+                ),
+                ("server".to_string(), "nginx".to_string()),
+            ],
+            warnings: vec![],
+            binary: None,
+            elapsed: None,
+        };
+
+        assert_eq!(
+            transformer.apply(&response).unwrap(),
+            "Status: 301 Moved Permanently\nLocation: https://example.com/new\n\nMoved"
+        );
+    }
+
+    #[test]
+    fn pdf_text() {
+        let entry = WatchEntry {
+            render: Render::PdfText,
+            ..WatchEntry::default()
+        };
+        let transformer = Transformer::new(&entry).unwrap();
+
+        // Content served as text is rendered as well, and fails if it is not a PDF
+        let response = Response {
+            content: "<html>Not found</html>".to_string(),
+            status: None,
+            metadata: vec![("content-type".to_string(), "text/html".to_string())],
+            warnings: vec![],
+            binary: None,
+            elapsed: None,
+        };
+        assert!(matches!(
+            transformer.apply(&response),
+            Err(DominionRequestError::Render(reason)) if reason == "not a PDF document"
+        ));
+    }
+}
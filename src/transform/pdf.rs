@@ -0,0 +1,183 @@
+use lopdf::Document;
+
+use crate::error::DominionRequestError;
+
+/// Extracts the text of a PDF document, page by page, each under a `--- Page N ---` marker.
+///
+/// Text in PDFs is laid out in arbitrary chunks, so it is rejoined and split into one sentence per
+/// line instead. That way, diffs show the sentences that changed, regardless of the layout.
+///
+/// Sentences are split after any word ending with '.', '?' or '!', so abbreviations like "e.g."
+/// or "Inc." split them too. Pages are numbered in reading order, so inserting or removing a page
+/// shifts the markers of all the pages after it, which then show up in the diff as well.
+pub fn pdf_to_text(bytes: &[u8]) -> Result<String, DominionRequestError> {
+    // The header may follow some garbage, but must be within the first 1024 bytes
+    let header = &bytes[..bytes.len().min(1024)];
+    if !header.windows(5).any(|window| window == b"%PDF-") {
+        return Err(DominionRequestError::Render(
+            "not a PDF document".to_string(),
+        ));
+    }
+
+    let render_err = |e: lopdf::Error| DominionRequestError::Render(e.to_string());
+    let document = Document::load_mem(bytes).map_err(render_err)?;
+
+    let mut text = String::new();
+    for page_number in document.get_pages().into_keys() {
+        let page = document.extract_text(&[page_number]).map_err(render_err)?;
+
+        text += format!("--- Page {page_number} ---\n").as_str();
+        for sentence in sentences(page.as_str()) {
+            text += sentence.as_str();
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+
+    Ok(text)
+}
+
+fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = vec![];
+    let mut sentence = String::new();
+
+    for word in text.split_whitespace() {
+        if !sentence.is_empty() {
+            sentence.push(' ');
+        }
+        sentence += word;
+
+        if word.ends_with(['.', '?', '!']) {
+            sentences.push(std::mem::take(&mut sentence));
+        }
+    }
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+
+    sentences
+}
+
+#[cfg(test)]
+mod test {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Object, Stream};
+
+    use crate::transform::pdf::*;
+
+    /// Builds a PDF with a page for each list of lines. The pages are stored in reverse, as their
+    /// order is only given by the page tree.
+    fn build_pdf(pages: &[&[&str]], compress: bool) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut kids = vec![];
+        for lines in pages.iter().rev() {
+            let mut operations = vec![];
+            for (idx, line) in lines.iter().enumerate() {
+                let y = 800 - 14 * idx as i64;
+                operations.extend([
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                    Operation::new("Td", vec![50.into(), y.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*line)]),
+                    Operation::new("ET", vec![]),
+                ]);
+            }
+
+            let content = Content { operations }.encode().unwrap();
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            });
+            kids.insert(0, page_id.into());
+        }
+
+        let count = kids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        if compress {
+            doc.compress();
+        }
+
+        let mut bytes = vec![];
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn extract() {
+        let pdf = build_pdf(
+            &[
+                &[
+                    "Pricing policy",
+                    "effective from May. The basic",
+                    "plan costs 5 EUR.",
+                ],
+                &["Questions? Contact us."],
+            ],
+            false,
+        );
+
+        assert_eq!(
+            pdf_to_text(pdf.as_slice()).unwrap(),
+            "--- Page 1 ---\nPricing policy effective from May.\nThe basic plan costs 5 EUR.\n\n\
+            --- Page 2 ---\nQuestions?\nContact us.\n\n"
+        );
+        assert!(matches!(
+            pdf_to_text(b"not a pdf"),
+            Err(DominionRequestError::Render(reason)) if reason == "not a PDF document"
+        ));
+    }
+
+    #[test]
+    fn compressed() {
+        // Repeated, so that compressing the page pays off
+        let clauses = ["This clause applies to all plans."; 8];
+        let pdf = build_pdf(
+            &[
+                &["Terms of service"],
+                &[
+                    "Provided by Example Inc. under these terms,",
+                    "e.g. fair use.",
+                ],
+                &clauses,
+            ],
+            true,
+        );
+        assert!(pdf.windows(11).any(|window| window == b"FlateDecode"));
+
+        assert_eq!(
+            pdf_to_text(pdf.as_slice()).unwrap(),
+            format!(
+                "--- Page 1 ---\nTerms of service\n\n\
+                --- Page 2 ---\nProvided by Example Inc.\nunder these terms, e.g.\nfair use.\n\n\
+                --- Page 3 ---\n{}\n",
+                "This clause applies to all plans.\n".repeat(8)
+            )
+        );
+    }
+}
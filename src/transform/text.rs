@@ -0,0 +1,37 @@
+use crate::error::DominionRequestError;
+
+/// Wide enough to keep each paragraph in a single line, so that diffs are not affected by wrapping.
+const TEXT_WIDTH: usize = 10_000;
+
+/// Converts an HTML document into readable plain text, keeping the headings, lists and link
+/// targets.
+pub fn html_to_text(content: &str) -> Result<String, DominionRequestError> {
+    html2text::config::plain()
+        .link_footnotes(true)
+        .no_link_wrapping()
+        .raw_mode(true)
+        .string_from_read(content.as_bytes(), TEXT_WIDTH)
+        .map_err(|e| DominionRequestError::Render(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transform::text::*;
+
+    #[test]
+    fn render() {
+        let content = r#"<html>
+            <head><script>var nonce = "abc";</script></head>
+            <body>
+                <h1>Pricing</h1>
+                <p>See the <a href="https://example.com/plans">plans</a> below.</p>
+                <ul><li>Basic</li><li>Pro</li></ul>
+            </body>
+        </html>"#;
+
+        assert_eq!(
+            html_to_text(content).unwrap(),
+            "# Pricing\n\nSee the [plans][1] below.\n* Basic\n* Pro\n\n[1]: https://example.com/plans\n"
+        );
+    }
+}
@@ -2,69 +2,68 @@ use std::collections::hash_map;
 use std::hash::{Hash, Hasher};
 
 use regex::Regex;
-use reqwest::header::CONTENT_TYPE;
-use reqwest::{Client, Method};
-use serde::Serialize;
-use serde_json::ser::PrettyFormatter;
-use serde_json::Serializer;
 use tokio::sync::mpsc;
-use tracing::{debug, info, trace};
-
-use crate::config::{HttpConfig, WatchEntry};
-use crate::error::{DominionAsyncError, DominionRequestError};
+use tokio::time::Instant;
+use tracing::{debug, error, info, trace};
+
+use crate::config::{HistoryConfig, HttpConfig, WatchEntry};
+use crate::error::{DominionAsyncError, DominionError, DominionRequestError};
+use crate::expect::Expectations;
+use crate::source::{BinaryContent, Response, Source, Status};
+use crate::store::{HistoryRecord, Store};
+use crate::transform::Transformer;
 use crate::NotificationEvent;
 
-static DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Watcher {
     pub url: String,
-    method: Method,
-    headers: Vec<(String, String)>,
-    http_client: Client,
+    source: Box<dyn Source>,
+    transformer: Transformer,
+    expectations: Option<Expectations>,
     notifier: mpsc::Sender<NotificationEvent>,
     ignore_mask: Option<Regex>,
+    store: Store,
+    store_key: String,
+    history: HistoryConfig,
     last_failed: bool,
+    last_warnings: Vec<String>,
     previous: Option<String>,
     previous_hash: u64,
+    /// The raw content seen last, if it was not text.
+    previous_binary: Option<BinaryContent>,
+    /// The state of the source stored last.
+    source_state: Option<String>,
 }
 
 impl Watcher {
-    pub fn new(
+    /// Creates a watcher, restoring the content it saw before the last restart.
+    pub async fn new(
         entry: &WatchEntry,
         notifier: mpsc::Sender<NotificationEvent>,
         http_cfg: &HttpConfig,
-    ) -> Result<Self, DominionRequestError> {
-        let headers = entry
-            .headers
-            .iter()
-            .map(|h| {
-                let (name, value) = h
-                    .split_once('=')
-                    .expect("malformed header; should be 'name=value'");
-                (name.to_string(), value.to_string())
-            })
-            .collect::<Vec<_>>();
-
-        let mut user_agent = http_cfg.user_agent.clone().unwrap_or_default();
-        if user_agent.is_empty() {
-            user_agent = DEFAULT_USER_AGENT.to_string();
-        }
-        debug!("Using user agent: {user_agent}");
-
-        let http_client = Client::builder().user_agent(user_agent).build()?;
-
-        Ok(Self {
+        store: Store,
+    ) -> Result<Self, DominionError> {
+        let mut watcher = Self {
             url: entry.url.clone(),
-            method: entry.method.clone(),
-            headers,
-            http_client,
+            source: crate::source::from_entry(entry, http_cfg)?,
+            transformer: Transformer::new(entry)?,
+            expectations: entry.expect.as_ref().map(Expectations::new).transpose()?,
             notifier,
             ignore_mask: Self::build_mask(entry.ignore.as_slice())?,
+            store,
+            store_key: Store::key(entry),
+            history: entry.history.clone(),
             last_failed: false,
+            last_warnings: vec![],
             previous: None,
             previous_hash: 0,
-        })
+            previous_binary: None,
+            source_state: None,
+        };
+        watcher.load_snapshot().await;
+        watcher.load_state().await;
+        watcher.prune_history().await;
+        Ok(watcher)
     }
 
     fn build_mask(ignore_patterns: &[String]) -> Result<Option<Regex>, DominionRequestError> {
@@ -97,10 +96,39 @@ impl Watcher {
         }
 
         match self.fetch().await {
-            Ok(content) => {
+            Ok(response) if response.is_not_modified() => {
                 self.last_failed = false;
+                debug!("No changes in {}, as reported by the source", self.url);
+                self.notifier
+                    .send(NotificationEvent::NoChanges {
+                        url: self.url.clone(),
+                    })
+                    .await?;
+            }
+            Ok(response) => {
+                self.last_failed = false;
+                trace!(
+                    "Fetched {} with status {:?} and metadata {:?}",
+                    self.url,
+                    response.status,
+                    response.metadata
+                );
+
+                // Only warn once about each issue, until it is resolved
+                for warning in &response.warnings {
+                    if !self.last_warnings.contains(warning) {
+                        self.notifier
+                            .send(NotificationEvent::Warning {
+                                url: self.url.clone(),
+                                message: warning.clone(),
+                            })
+                            .await?;
+                    }
+                }
+                self.last_warnings = response.warnings;
 
-                let current = content;
+                let binary = response.binary;
+                let current = response.content;
                 let current_masked = self.mask_value(current.clone());
                 let current_hash = Watcher::hash(current_masked.as_str());
 
@@ -113,21 +141,46 @@ impl Watcher {
                             })
                             .await?;
                     } else {
-                        self.notifier
-                            .send(NotificationEvent::Changed {
+                        let events = match &binary {
+                            Some(new) => vec![NotificationEvent::BinaryChanged {
                                 url: self.url.clone(),
-                                old: prev.clone(),
-                                new: current.clone(),
-                            })
-                            .await?;
+                                old: self.previous_binary.take().map(Box::new),
+                                new: Box::new(new.clone()),
+                            }],
+                            None => self.source.changes(
+                                self.url.as_str(),
+                                prev.as_str(),
+                                current.as_str(),
+                            ),
+                        };
+                        if events.is_empty() {
+                            // Nothing worth notifying, e.g. items that were dropped from a feed
+                            self.notifier
+                                .send(NotificationEvent::NoChanges {
+                                    url: self.url.clone(),
+                                })
+                                .await?;
+                        }
+                        for event in events {
+                            self.notifier.send(event).await?;
+                        }
 
+                        self.record_change(current.as_str()).await;
+                        self.save_snapshot(current.as_str(), binary.as_ref()).await;
                         self.previous = Some(current);
                         self.previous_hash = current_hash;
+                        self.previous_binary = binary;
                     }
                 } else {
+                    self.save_snapshot(current.as_str(), binary.as_ref()).await;
                     self.previous = Some(current);
                     self.previous_hash = current_hash;
+                    self.previous_binary = binary;
                 }
+
+                // Only now that the content is stored, may the source skip sending it again
+                self.source.commit();
+                self.save_state().await;
             }
             Err(err) => {
                 if !self.last_failed {
@@ -138,10 +191,18 @@ impl Watcher {
                             NotificationEvent::Failed {
                                 url: url.clone(),
                                 reason: format!("{err}"),
-                                status: Some(*status),
+                                status: Some(Status::Http(*status)),
                                 body: Some(body.clone()),
                             }
                         }
+                        DominionRequestError::CommandFailed { status, stderr, .. } => {
+                            NotificationEvent::Failed {
+                                url: self.url.clone(),
+                                reason: format!("{err}"),
+                                status: Some(Status::Exit(*status)),
+                                body: Some(stderr.clone()),
+                            }
+                        }
                         _ => NotificationEvent::Failed {
                             url: self.url.clone(),
                             reason: format!("{err}"),
@@ -158,48 +219,162 @@ impl Watcher {
         Ok(())
     }
 
-    async fn fetch(&self) -> Result<String, DominionRequestError> {
-        let mut req = self
-            .http_client
-            .request(self.method.clone(), self.url.as_str());
+    /// Restores the content seen before the last restart, so that any changes made while we were
+    /// down are still picked up.
+    async fn load_snapshot(&mut self) {
+        match self.store.load_snapshot(self.store_key.as_str()).await {
+            Ok(Some(snapshot)) => {
+                debug!("Loaded stored snapshot of {}", self.url);
+                self.previous_hash = Watcher::hash(self.mask_value(snapshot.clone()).as_str());
+                self.previous_binary = self.load_blob(snapshot.as_str()).await;
+                self.previous = Some(snapshot);
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to load stored snapshot of {}: {err}", self.url),
+        }
+    }
 
-        for (name, value) in &self.headers {
-            req = req.header(name, value);
+    /// Restores the raw content matching a snapshot, if it was not text. The content type is
+    /// taken from the snapshot, which describes the content.
+    async fn load_blob(&self, snapshot: &str) -> Option<BinaryContent> {
+        let content_type = snapshot
+            .lines()
+            .find_map(|line| line.strip_prefix("Content type: "))?;
+
+        match self.store.load_blob(self.store_key.as_str()).await {
+            Ok(Some(bytes)) => {
+                let content_type = Some(content_type)
+                    .filter(|content_type| *content_type != "unknown")
+                    .map(str::to_string);
+                Some(BinaryContent::new(bytes, content_type))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                error!("Failed to load stored content of {}: {err}", self.url);
+                None
+            }
         }
+    }
 
-        trace!("Fetching {}: {:?}", self.url, req);
-        let res = req.send().await?;
-        let status = res.status();
-        trace!("Fetched {}: {:?}", self.url, res);
+    /// Restores the state the source kept before the last restart, e.g. the feed items seen.
+    async fn load_state(&mut self) {
+        match self.store.load_state(self.store_key.as_str()).await {
+            Ok(Some(state)) => {
+                debug!("Loaded stored state of {}", self.url);
+                if let Err(err) = self.source.restore(state.as_str()) {
+                    error!("Failed to restore stored state of {}: {err}", self.url);
+                }
+                self.source_state = Some(state);
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to load stored state of {}: {err}", self.url),
+        }
+    }
 
-        let is_json = res
-            .headers()
-            .get(CONTENT_TYPE)
-            .map(|ct| ct.to_str().unwrap_or_default().contains("json"))
-            .unwrap_or(false);
+    /// Stores the state of the source, if it changed since it was stored last.
+    async fn save_state(&mut self) {
+        let Some(state) = self
+            .source
+            .state()
+            .filter(|state| self.source_state.as_ref() != Some(state))
+        else {
+            return;
+        };
 
-        let text = if is_json {
-            let json = res.json::<serde_json::Value>().await?;
+        let result = self
+            .store
+            .save_state(self.store_key.as_str(), state.as_str())
+            .await;
+        match result {
+            Ok(()) => self.source_state = Some(state),
+            Err(err) => error!("Failed to store state of {}: {err}", self.url),
+        }
+    }
 
-            let mut buf = Vec::new();
-            let formatter = PrettyFormatter::with_indent(b"    ");
-            let mut ser = Serializer::with_formatter(&mut buf, formatter);
-            json.serialize(&mut ser)?;
+    async fn save_snapshot(&self, content: &str, binary: Option<&BinaryContent>) {
+        if let Err(err) = self
+            .store
+            .save_snapshot(self.store_key.as_str(), content)
+            .await
+        {
+            error!("Failed to store snapshot of {}: {err}", self.url);
+        }
 
-            String::from_utf8(buf).map_err(|_err| DominionRequestError::InvalidJson(json))?
-        } else {
-            res.text().await?
+        let result = match binary {
+            Some(binary) => {
+                self.store
+                    .save_blob(self.store_key.as_str(), binary.bytes.as_slice())
+                    .await
+            }
+            None => self.store.delete_blob(self.store_key.as_str()).await,
         };
+        if let Err(err) = result {
+            error!("Failed to store content of {}: {err}", self.url);
+        }
+    }
 
-        if !status.is_success() {
-            return Err(DominionRequestError::HttpRequestFailed {
+    /// Discards the changes that expired while we were down, or since the last one was recorded.
+    async fn prune_history(&self) {
+        if let Err(err) = self
+            .store
+            .prune_history(self.store_key.as_str(), &self.history)
+            .await
+        {
+            error!("Failed to prune history of {}: {err}", self.url);
+        }
+    }
+
+    async fn record_change(&self, content: &str) {
+        let previous = self.previous.as_deref().unwrap_or_default();
+        let record = HistoryRecord::new(self.url.as_str(), previous, content);
+        if let Err(err) = self
+            .store
+            .record_change(self.store_key.as_str(), &record, &self.history)
+            .await
+        {
+            error!("Failed to record change of {} in history: {err}", self.url);
+        }
+    }
+
+    /// Fetches the content from the source, and keeps only the relevant parts of it.
+    async fn fetch(&mut self) -> Result<Response, DominionRequestError> {
+        let start = Instant::now();
+        let mut response = self.source.fetch().await?;
+        let mut elapsed = start.elapsed();
+
+        // Nothing to compare against, e.g. if storing the content failed, so fetch it in full
+        if response.is_not_modified() && self.previous.is_none() {
+            debug!("No stored content of {}, fetching it again", self.url);
+            self.source.reset();
+            let start = Instant::now();
+            response = self.source.fetch().await?;
+            elapsed = start.elapsed();
+        }
+
+        if let Some(expectations) = &self.expectations {
+            let failed = |reason| DominionRequestError::ExpectationFailed {
                 url: self.url.clone(),
-                status,
-                body: text,
-            });
+                reason,
+            };
+
+            let elapsed = response.elapsed.unwrap_or(elapsed);
+            expectations.check(&response, elapsed).map_err(failed)?;
+
+            if !response.is_not_modified() && expectations.checks_body() {
+                let body = self.transformer.body(&response)?;
+                expectations.check_body(body.as_ref()).map_err(failed)?;
+            }
         }
 
-        Ok(text)
+        if response.is_not_modified() {
+            return Ok(response);
+        }
+
+        response.content = self.transformer.apply(&response)?;
+        if self.transformer.renders_binary() {
+            response.binary = None;
+        }
+        Ok(response)
     }
 
     fn mask_value(&self, value: String) -> String {
@@ -218,10 +393,16 @@ impl Watcher {
 
 #[cfg(test)]
 mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::config::{ExpectConfig, SessionConfig, SessionStep};
+    use crate::source::mock;
     use crate::watch::*;
 
-    #[test]
-    fn mask() {
+    #[tokio::test]
+    async fn mask() {
         let ignore_patterns = vec![
             "foo".to_string(),
             "bar".to_string(),
@@ -233,7 +414,8 @@ mod test {
         };
         let (tx, _) = mpsc::channel::<NotificationEvent>(1);
         let http_cfg = HttpConfig::default();
-        let watcher = Watcher::new(&entry, tx, &http_cfg).unwrap();
+        let store = Store::new(std::env::temp_dir().join("dominion-test")).unwrap();
+        let watcher = Watcher::new(&entry, tx, &http_cfg, store).await.unwrap();
 
         let value = r#"{
 	"key": "value",
@@ -265,4 +447,274 @@ mod test {
 
         assert_eq!(watcher.mask_value(value), expected);
     }
+
+    /// Creates an empty directory for a test to keep its files in.
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("dominion-test-watch-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn restart() {
+        let dir = test_dir("restart");
+        let file = dir.join("page.txt");
+        std::fs::write(&file, "before").unwrap();
+
+        let entry = WatchEntry {
+            protocol: "file".to_string(),
+            url: format!("{}", file.display()),
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let http_cfg = HttpConfig::default();
+
+        let mut watcher = Watcher::new(&entry, tx.clone(), &http_cfg, store.clone())
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+        assert!(rx.try_recv().is_err());
+        drop(watcher);
+
+        // Changes made while down are compared against the stored content
+        std::fs::write(&file, "after").unwrap();
+        let mut watcher = Watcher::new(&entry, tx, &http_cfg, store).await.unwrap();
+        watcher.watch().await.unwrap();
+        match rx.try_recv().unwrap() {
+            NotificationEvent::Changed { old, new, .. } => {
+                assert_eq!(old, "before");
+                assert_eq!(new, "after");
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+
+    /// Serves "Hello" with an ETag, or a 304 if the client already has it. Counts the requests
+    /// that were conditional.
+    async fn conditional_server() -> (String, Arc<AtomicUsize>) {
+        let conditional = Arc::new(AtomicUsize::new(0));
+        let counter = conditional.clone();
+        let server = mock::serve(move |request| {
+            let response = if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                counter.fetch_add(1, Ordering::SeqCst);
+                mock::response("304 Not Modified", &[("ETag", "\"v1\"")], "")
+            } else {
+                mock::response("200 OK", &[("ETag", "\"v1\"")], "Hello")
+            };
+            Some(response)
+        })
+        .await;
+        (server, conditional)
+    }
+
+    #[tokio::test]
+    async fn uncommitted_validators() {
+        let dir = test_dir("uncommitted-validators");
+        let (server, conditional) = conditional_server().await;
+
+        let entry = WatchEntry {
+            url: server,
+            expect: Some(ExpectConfig {
+                body_contains: vec!["Welcome".to_string()],
+                ..ExpectConfig::default()
+            }),
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let mut watcher = Watcher::new(&entry, tx, &HttpConfig::default(), store)
+            .await
+            .unwrap();
+
+        // Content failing the expectations is not stored, so it must be fetched in full again
+        for _ in 0..2 {
+            watcher.watch().await.unwrap();
+        }
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::Failed { .. })
+        ));
+        assert_eq!(conditional.load(Ordering::SeqCst), 0);
+        assert!(watcher.previous.is_none());
+    }
+
+    #[tokio::test]
+    async fn not_modified_without_previous() {
+        let dir = test_dir("not-modified-without-previous");
+        let (server, conditional) = conditional_server().await;
+
+        let entry = WatchEntry {
+            url: server,
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let mut watcher = Watcher::new(&entry, tx, &HttpConfig::default(), store)
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+        watcher.watch().await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::NoChanges { .. })
+        ));
+        assert_eq!(conditional.load(Ordering::SeqCst), 1);
+
+        // Without the content to compare against, a 304 is of no use
+        watcher.previous = None;
+        watcher.watch().await.unwrap();
+        assert_eq!(conditional.load(Ordering::SeqCst), 2);
+        assert_eq!(watcher.previous.as_deref(), Some("Hello"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn response_time() {
+        let dir = test_dir("response-time");
+        // Logging in is slow, but the page itself is not
+        let server = mock::serve(|request| {
+            if request.starts_with("GET /login") {
+                std::thread::sleep(Duration::from_millis(500));
+            }
+            Some(mock::response("200 OK", &[], "Hello"))
+        })
+        .await;
+
+        let entry = WatchEntry {
+            url: format!("{server}/page"),
+            session: Some(SessionConfig {
+                steps: vec![SessionStep {
+                    url: format!("{server}/login"),
+                    ..SessionStep::default()
+                }],
+                login_url: None,
+            }),
+            expect: Some(ExpectConfig {
+                max_response_time: Some(Duration::from_millis(400)),
+                ..ExpectConfig::default()
+            }),
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let mut watcher = Watcher::new(&entry, tx, &HttpConfig::default(), store)
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(watcher.previous.as_deref(), Some("Hello"));
+    }
+
+    #[tokio::test]
+    async fn binary_changed() {
+        let dir = test_dir("binary-changed");
+        let file = dir.join("image.bin");
+        std::fs::write(&file, b"\x89\0\xff1").unwrap();
+
+        let entry = WatchEntry {
+            protocol: "file".to_string(),
+            url: format!("{}", file.display()),
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let http_cfg = HttpConfig::default();
+
+        let mut watcher = Watcher::new(&entry, tx.clone(), &http_cfg, store.clone())
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+        drop(watcher);
+
+        // The previous raw content is restored along with the snapshot
+        std::fs::write(&file, b"\x89\0\xff2").unwrap();
+        let mut watcher = Watcher::new(&entry, tx, &http_cfg, store.clone())
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+        match rx.try_recv().unwrap() {
+            NotificationEvent::BinaryChanged { old, new, .. } => {
+                assert_eq!(old.unwrap().bytes, b"\x89\0\xff1");
+                assert_eq!(new.bytes, b"\x89\0\xff2");
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        // Text content leaves no raw content behind
+        std::fs::write(&file, "text").unwrap();
+        watcher.watch().await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::Changed { .. })
+        ));
+        assert_eq!(
+            store.load_blob(watcher.store_key.as_str()).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn feed_items_seen() {
+        let dir = test_dir("feed-items-seen");
+        let item = |id: usize| {
+            format!("<item><guid>https://example.com/{id}</guid><title>Post {id}</title></item>")
+        };
+        let items = Arc::new(std::sync::Mutex::new(vec![1]));
+        let served = items.clone();
+        let server = mock::serve(move |_| {
+            let items = served
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|id| item(*id))
+                .collect::<String>();
+            let feed =
+                format!("<rss version=\"2.0\"><channel><title>News</title>{items}</channel></rss>");
+            Some(mock::response(
+                "200 OK",
+                &[("Content-Type", "application/rss+xml")],
+                feed.as_str(),
+            ))
+        })
+        .await;
+
+        let entry = WatchEntry {
+            protocol: "feed".to_string(),
+            url: server,
+            ..WatchEntry::default()
+        };
+        let store = Store::new(dir.join("data")).unwrap();
+        let (tx, mut rx) = mpsc::channel::<NotificationEvent>(8);
+        let http_cfg = HttpConfig::default();
+        let mut watcher = Watcher::new(&entry, tx.clone(), &http_cfg, store.clone())
+            .await
+            .unwrap();
+        watcher.watch().await.unwrap();
+
+        *items.lock().unwrap() = vec![2, 1];
+        watcher.watch().await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::NewItem { title: Some(title), .. }) if title == "Post 2"
+        ));
+
+        // The first post drops out of the feed, and is bumped back after a restart
+        *items.lock().unwrap() = vec![2];
+        watcher.watch().await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::NoChanges { .. })
+        ));
+        drop(watcher);
+
+        *items.lock().unwrap() = vec![1, 2];
+        let mut watcher = Watcher::new(&entry, tx, &http_cfg, store).await.unwrap();
+        watcher.watch().await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationEvent::NoChanges { .. })
+        ));
+    }
 }